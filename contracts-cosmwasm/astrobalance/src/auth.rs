@@ -0,0 +1,164 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_binary, Addr, Api, Binary, Env, MessageInfo, StdResult, Storage};
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+use crate::state::VIEWING_KEYS;
+
+/// Derive a fresh viewing key from the sender-supplied entropy mixed with
+/// block data the sender cannot predict at the time they chose it, the same
+/// construction secret-toolkit's `ViewingKey::new` uses.
+pub fn generate_viewing_key(env: &Env, info: &MessageInfo, entropy: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(info.sender.as_bytes());
+    hasher.update(entropy.as_bytes());
+    hasher.update(env.block.height.to_be_bytes());
+    hasher.update(env.block.time.nanos().to_be_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Hash a raw viewing key the same way SNIP-20's `ViewingKey` does: a single
+/// SHA-256 over the UTF-8 bytes, stored hex-encoded so the original key can
+/// never be recovered from storage.
+pub fn hash_viewing_key(raw: &str) -> String {
+    let digest = Sha256::digest(raw.as_bytes());
+    hex::encode(digest)
+}
+
+pub fn set_viewing_key(storage: &mut dyn Storage, addr: &Addr, raw_key: &str) -> StdResult<()> {
+    VIEWING_KEYS.save(storage, addr, &hash_viewing_key(raw_key))
+}
+
+/// Checks `raw_key` against the address's stored viewing key. An address
+/// that has never set a key has not opted into the privacy gate, so reads
+/// stay open to it for backwards compatibility with existing integrations.
+pub fn verify_viewing_key(
+    storage: &dyn Storage,
+    addr: &Addr,
+    raw_key: Option<&str>,
+) -> Result<(), ContractError> {
+    let Some(hashed) = VIEWING_KEYS.may_load(storage, addr)? else {
+        return Ok(());
+    };
+
+    match raw_key {
+        Some(key) if hash_viewing_key(key) == hashed => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// The set of private queries a permit can authorize, mirroring SNIP-24's
+/// `Permission` enum.
+#[cw_serde]
+pub enum PermitQuery {
+    UserInfo,
+    TxHistory,
+}
+
+#[cw_serde]
+pub struct PermitParams {
+    pub permit_name: String,
+    pub signer: Addr,
+    pub allowed_queries: Vec<PermitQuery>,
+    pub chain_id: String,
+}
+
+#[cw_serde]
+pub struct PermitSignature {
+    pub pub_key: Binary,
+    pub signature: Binary,
+}
+
+#[cw_serde]
+pub struct QueryPermit {
+    pub params: PermitParams,
+    pub signature: PermitSignature,
+}
+
+impl QueryPermit {
+    /// Verifies the secp256k1 signature covers this permit's params exactly
+    /// (binding the signer and allow-list together so neither can be
+    /// substituted), and that the permit authorizes `query` for
+    /// `expected_signer`.
+    pub fn verify(
+        &self,
+        api: &dyn Api,
+        expected_signer: &Addr,
+        query: PermitQuery,
+    ) -> Result<(), ContractError> {
+        if self.params.signer != *expected_signer {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if !self.params.allowed_queries.contains(&query) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let sign_bytes = to_json_binary(&self.params)?;
+        let message_hash = Sha256::digest(sign_bytes.as_slice());
+
+        let valid = api
+            .secp256k1_verify(
+                &message_hash,
+                self.signature.signature.as_slice(),
+                self.signature.pub_key.as_slice(),
+            )
+            .unwrap_or(false);
+
+        if !valid {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    #[test]
+    fn verify_viewing_key_allows_unset_address() {
+        let deps = mock_dependencies();
+        let addr = Addr::unchecked("alice");
+        assert!(verify_viewing_key(&deps.storage, &addr, None).is_ok());
+    }
+
+    #[test]
+    fn verify_viewing_key_requires_match_once_set() {
+        let mut deps = mock_dependencies();
+        let addr = Addr::unchecked("alice");
+        set_viewing_key(&mut deps.storage, &addr, "correct-key").unwrap();
+
+        assert!(verify_viewing_key(&deps.storage, &addr, Some("correct-key")).is_ok());
+        assert_eq!(
+            verify_viewing_key(&deps.storage, &addr, Some("wrong-key")),
+            Err(ContractError::Unauthorized {})
+        );
+        assert_eq!(
+            verify_viewing_key(&deps.storage, &addr, None),
+            Err(ContractError::Unauthorized {})
+        );
+    }
+
+    #[test]
+    fn permit_rejects_query_outside_allow_list() {
+        let permit = QueryPermit {
+            params: PermitParams {
+                permit_name: "test".to_string(),
+                signer: Addr::unchecked("alice"),
+                allowed_queries: vec![PermitQuery::UserInfo],
+                chain_id: "test-chain".to_string(),
+            },
+            signature: PermitSignature {
+                pub_key: Binary::default(),
+                signature: Binary::default(),
+            },
+        };
+
+        let deps = mock_dependencies();
+        let result = permit.verify(&deps.api, &Addr::unchecked("alice"), PermitQuery::TxHistory);
+        assert_eq!(result, Err(ContractError::Unauthorized {}));
+    }
+}
@@ -1,30 +1,68 @@
 use cosmwasm_std::entry_point;
 #[warn(unused_imports)]
 use cosmwasm_std::{
-    to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, Fraction,
-    MessageInfo, Order, Response, StdError, StdResult, Uint128,
+    from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    Fraction, MessageInfo, Order, Reply, Response, StdError, StdResult, SubMsg, SubMsgResult,
+    Timestamp, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 use std::collections::HashMap;
 
+use crate::auth::{self, PermitQuery};
 use crate::error::ContractError;
 use crate::msg::{
-    ExecuteMsg, GetProtocolInfoResponse, GetProtocolsResponse, GetRebalanceHistoryResponse,
-    GetRiskParametersResponse, GetTotalValueResponse, GetUserInfoResponse, InstantiateMsg,
-    QueryMsg, RiskParametersMsg,
+    Cw20HookMsg, ExecuteMsg, GetAccruedFeesResponse, GetClaimsResponse, GetDepositQuoteResponse,
+    GetFeeConfigResponse, GetFeeRecipientsResponse, GetHarvestHistoryResponse,
+    GetPairContractResponse, GetPriceFeedsResponse, GetProtocolBalancesResponse,
+    GetProtocolInfoResponse, GetProtocolsResponse, GetRebalanceHistoryResponse,
+    GetRebalancePlanResponse, GetRebalanceSimulationResponse, GetRiskParametersResponse,
+    GetRolesResponse, GetShareValueResponse, GetSharesResponse, GetTotalValueResponse,
+    GetTwapPriceResponse, GetUserInfoResponse, GetUserTransactionsResponse,
+    GetUserTxHistoryResponse, InstantiateMsg, MigrateMsg, PermitQueryResponse, PriceFeedEntry,
+    ProtocolBalance, ProtocolBalanceSnapshot, QueryMsg, RebalanceSimulationLeg,
+    ReconcileTotalValueResponse, RebalancePlanMove, RiskParametersMsg,
+};
+use crate::limiters;
+use crate::oracle;
+use crate::permissions::{grant_role, require_role, revoke_role, Role, ROLES};
+use crate::protocols::{
+    create_protocol_adapter,
+    cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg},
+    AstroportAmmAdapter,
 };
-use crate::protocols::create_protocol_adapter;
 use crate::state::{
-    Config, ProtocolInfo, RebalanceRecord, RiskParameters, UserDeposit, UserInfo, CONFIG,
-    PROTOCOLS, REBALANCE_HISTORY, RISK_PARAMETERS, TOTAL_USDC_VALUE, USER_INFOS,
+    AssetInfo, Claim, Config, ContractStatus, ContractStatusInfo, HarvestInProgress,
+    PendingDeposit, PendingRebalanceLeg, ProtocolInfo, RebalanceInProgress, RebalanceLegKind,
+    RiskParameters, UserDeposit, UserInfo, ACCRUED_FEES, CLAIMS, CONFIG, CONTRACT_STATE_VERSION,
+    CONTRACT_STATUS, FEE_RECIPIENTS, HARVEST_IN_PROGRESS, ORACLE_ADDR, ORACLE_MAX_STALENESS,
+    PAIR_REGISTRY, PENDING_DEPOSIT, PRICE_FEED_IDS, PROTOCOLS, REBALANCE_IN_PROGRESS,
+    RISK_PARAMETERS, SHARES, TOTAL_SHARES, TOTAL_USDC_VALUE, USER_INFOS, VAULT_HIGH_WATER_MARK,
+};
+use crate::strategy_executor::{
+    RebalanceAction, StrategyExecutor, HARVEST_REPLY_ID, REBALANCE_LEG_REPLY_ID,
 };
-use crate::strategy_executor::StrategyExecutor;
 use crate::token_converter::AstroportRouter;
+use crate::twap;
+use crate::tx_log::{self, TxKind};
 
 // version info for migration
 const CONTRACT_NAME: &str = "crates.io:astrobalance";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Current schema version of `PROTOCOLS`/`TX_LOG`/etc.; bump this whenever
+// `migrate` needs to translate a stored layout into a new one.
+const STATE_VERSION: u64 = 1;
+
+// Reply id for the Astroport swap submessage a non-base-denom deposit
+// issues while converting into `base_denom`; `reply` credits the user
+// once the swap's actual output is known.
+const DEPOSIT_SWAP_REPLY_ID: u64 = 1;
+
+// Minimum history `twap::twap_since_genesis` must have accumulated for a
+// denom before `execute_rebalance` trusts it enough to enforce
+// `RiskParameters.max_price_deviation` against it.
+const TWAP_DEVIATION_WINDOW_SECS: u64 = 3600;
+
 // Helper function to conditionally validate addresses
 #[cfg(test)]
 fn addr_validate(_api: &dyn cosmwasm_std::Api, addr: &str) -> StdResult<Addr> {
@@ -46,6 +84,7 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    CONTRACT_STATE_VERSION.save(deps.storage, &STATE_VERSION)?;
 
     // During tests, skip validation to avoid Bech32 errors
     #[cfg(test)]
@@ -55,6 +94,11 @@ pub fn instantiate(
         base_denom: msg.base_denom.clone(),
         accepted_denoms: msg.accepted_denoms.clone(),
         astroport_router: msg.astroport_router.clone(),
+        unbonding_period: msg.unbonding_period,
+        performance_fee_bps: msg.performance_fee_bps,
+        fee_collector: Addr::unchecked(&msg.fee_collector),
+        pending_admin: None,
+        pending_ai_operator: None,
     };
 
     // In production, validate all addresses
@@ -65,24 +109,43 @@ pub fn instantiate(
         base_denom: msg.base_denom.clone(),
         accepted_denoms: msg.accepted_denoms.clone(),
         astroport_router: deps.api.addr_validate(&msg.astroport_router)?.to_string(),
+        unbonding_period: msg.unbonding_period,
+        performance_fee_bps: msg.performance_fee_bps,
+        fee_collector: deps.api.addr_validate(&msg.fee_collector)?,
+        pending_admin: None,
+        pending_ai_operator: None,
     };
 
     CONFIG.save(deps.storage, &config)?;
 
+    validate_max_slippage(msg.risk_parameters.max_slippage_bps)?;
+
     // Initialize risk parameters
     let risk_parameters = RiskParameters {
         max_allocation_per_protocol: msg.risk_parameters.max_allocation_per_protocol,
         max_slippage: msg.risk_parameters.max_slippage,
         rebalance_threshold: msg.risk_parameters.rebalance_threshold,
         emergency_withdrawal_fee: msg.risk_parameters.emergency_withdrawal_fee,
+        max_price_staleness: msg.risk_parameters.max_price_staleness,
+        performance_fee: msg.risk_parameters.performance_fee,
+        max_price_deviation: msg.risk_parameters.max_price_deviation,
+        max_slippage_bps: msg.risk_parameters.max_slippage_bps,
     };
     RISK_PARAMETERS.save(deps.storage, &risk_parameters)?;
 
-    // Initialize total USDC value
+    // Initialize total USDC value and the share ledger priced against it
     TOTAL_USDC_VALUE.save(deps.storage, &Uint128::zero())?;
+    TOTAL_SHARES.save(deps.storage, &Uint128::zero())?;
+
+    // Initialize the transaction ledger's sequence counter
+    tx_log::TX_SEQ.save(deps.storage, &0u64)?;
+
+    // Initialize accrued performance fees
+    ACCRUED_FEES.save(deps.storage, &Uint128::zero())?;
 
-    // Initialize empty rebalance history
-    REBALANCE_HISTORY.save(deps.storage, &Vec::<RebalanceRecord>::new())?;
+    // The ai_operator keeps rebalancing out of the box; additional rebalancer
+    // keys or a risk committee are layered on afterward via `GrantRole`.
+    grant_role(deps.storage, &config.ai_operator, Role::Rebalancer)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
@@ -91,6 +154,56 @@ pub fn instantiate(
         .add_attribute("base_denom", msg.base_denom))
 }
 
+/// Walks every `PROTOCOLS` entry and `TX_LOG` record, reading each under its
+/// old layout and rewriting it under the current one, then bumps
+/// `CONTRACT_STATE_VERSION` so a later code upgrade sharing the same version
+/// number doesn't redo the translation. Refuses to run if the stored state
+/// is already at `STATE_VERSION` — migrations are one-way, and re-applying
+/// one against already-translated data would corrupt it rather than being a
+/// harmless no-op.
+///
+/// Nothing in `ProtocolInfo`/`TxRecord` has actually changed shape since the
+/// version this was introduced at, so today's translation step is the
+/// identity function on every entry; it exists so the *next* field addition
+/// has a place to put its translation instead of deserializing old state
+/// straight into a new struct and failing.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored_version = CONTRACT_STATE_VERSION.may_load(deps.storage)?.unwrap_or(0);
+    if stored_version >= STATE_VERSION {
+        return Err(ContractError::AlreadyMigrated {
+            version: stored_version,
+        });
+    }
+
+    let protocol_names: Vec<String> = PROTOCOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
+    for name in &protocol_names {
+        let protocol = PROTOCOLS.load(deps.storage, name)?;
+        PROTOCOLS.save(deps.storage, name, &protocol)?;
+    }
+
+    let tx_keys: Vec<u64> = tx_log::TX_LOG
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
+    for seq in &tx_keys {
+        let record = tx_log::TX_LOG.load(deps.storage, *seq)?;
+        tx_log::TX_LOG.save(deps.storage, *seq, &record)?;
+    }
+
+    CONTRACT_STATE_VERSION.save(deps.storage, &STATE_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", STATE_VERSION.to_string())
+        .add_attribute("protocols_migrated", protocol_names.len().to_string())
+        .add_attribute("tx_log_entries_migrated", tx_keys.len().to_string()))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -100,16 +213,34 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         // User operations
-        ExecuteMsg::Deposit {} => execute_deposit(deps, env, info),
-        ExecuteMsg::Withdraw { amount, denom } => execute_withdraw(deps, env, info, amount, denom),
-        ExecuteMsg::EmergencyWithdraw {} => execute_emergency_withdraw(deps, env, info),
+        ExecuteMsg::Deposit { recipient } => execute_deposit(deps, env, info, recipient),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Withdraw {
+            amount,
+            denom,
+            sync_balances,
+            exact_output,
+        } => execute_withdraw(deps, env, info, amount, denom, sync_balances, exact_output),
+        ExecuteMsg::EmergencyWithdraw { sync_balances } => {
+            execute_emergency_withdraw(deps, env, info, sync_balances)
+        }
+        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
 
         // Protocol management
         ExecuteMsg::AddProtocol {
             name,
             contract_addr,
             initial_allocation,
-        } => execute_add_protocol(deps, env, info, name, contract_addr, initial_allocation),
+            deposit_asset,
+        } => execute_add_protocol(
+            deps,
+            env,
+            info,
+            name,
+            contract_addr,
+            initial_allocation,
+            deposit_asset,
+        ),
         ExecuteMsg::RemoveProtocol { name } => execute_remove_protocol(deps, env, info, name),
         ExecuteMsg::UpdateProtocol {
             name,
@@ -121,113 +252,246 @@ pub fn execute(
         ExecuteMsg::Rebalance {
             target_allocations,
             reason,
-        } => execute_rebalance(deps, env, info, target_allocations, reason),
+            sync_balances,
+        } => execute_rebalance(deps, env, info, target_allocations, reason, sync_balances),
         ExecuteMsg::UpdateRiskParameters { risk_parameters } => {
             execute_update_risk_parameters(deps, env, info, risk_parameters)
         }
 
         // Admin functions
-        ExecuteMsg::AddSupportedToken { denom } => {
-            execute_add_supported_token(deps, env, info, denom)
+        ExecuteMsg::SetFeeRecipients { recipients } => {
+            execute_set_fee_recipients(deps, info, recipients)
         }
-        ExecuteMsg::RemoveSupportedToken { denom } => {
-            execute_remove_supported_token(deps, env, info, denom)
+        ExecuteMsg::UpdateFeeConfig {
+            performance_fee,
+            recipients,
+        } => execute_update_fee_config(deps, info, performance_fee, recipients),
+        ExecuteMsg::ClaimFees {} => execute_claim_fees(deps, info),
+        ExecuteMsg::AddSupportedToken { asset } => {
+            execute_add_supported_token(deps, env, info, asset)
+        }
+        ExecuteMsg::RemoveSupportedToken { asset } => {
+            execute_remove_supported_token(deps, env, info, asset)
         }
         ExecuteMsg::UpdateAdmin { admin } => execute_update_admin(deps, env, info, admin),
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, info),
+        ExecuteMsg::CancelAdminChange {} => execute_cancel_admin_change(deps, info),
         ExecuteMsg::UpdateAiOperator { ai_operator } => {
             execute_update_ai_operator(deps, env, info, ai_operator)
         }
+        ExecuteMsg::AcceptAiOperator {} => execute_accept_ai_operator(deps, info),
+        ExecuteMsg::CancelAiOperatorChange {} => execute_cancel_ai_operator_change(deps, info),
+
+        // Oracle configuration
+        ExecuteMsg::SetOracleConfig {
+            oracle_addr,
+            max_staleness,
+        } => execute_set_oracle_config(deps, env, info, oracle_addr, max_staleness),
+        ExecuteMsg::SetPriceFeed { denom, feed_id } => {
+            execute_set_price_feed(deps, env, info, denom, feed_id)
+        }
+        ExecuteMsg::RegisterPair {
+            denom_a,
+            denom_b,
+            pair_contract,
+        } => execute_register_pair(deps, info, denom_a, denom_b, pair_contract),
+
+        // Rebalance rate-limiting
+        ExecuteMsg::RegisterStaticLimiter {
+            protocol,
+            upper_bound,
+        } => execute_register_static_limiter(deps, env, info, protocol, upper_bound),
+        ExecuteMsg::RegisterChangeLimiter {
+            protocol,
+            boundary_offset,
+            window_size,
+            division_count,
+        } => execute_register_change_limiter(
+            deps,
+            env,
+            info,
+            protocol,
+            boundary_offset,
+            window_size,
+            division_count,
+        ),
+        ExecuteMsg::DeregisterLimiter { protocol } => {
+            execute_deregister_limiter(deps, env, info, protocol)
+        }
+
+        // Private-query authentication
+        ExecuteMsg::SetViewingKey { key } => execute_set_viewing_key(deps, info, key),
+        ExecuteMsg::CreateViewingKey { entropy } => {
+            execute_create_viewing_key(deps, env, info, entropy)
+        }
+
+        // Contract-wide killswitch
+        ExecuteMsg::SetContractStatus { status, reason } => {
+            execute_set_contract_status(deps, env, info, status, reason)
+        }
+
+        // Balance synchronization
+        ExecuteMsg::SyncBalances {} => execute_sync_balances(deps, env, info),
+
+        ExecuteMsg::AutoRebalance {} => execute_auto_rebalance(deps, env, info),
+
+        ExecuteMsg::HarvestRewards { compound } => {
+            execute_harvest_rewards(deps, env, info, compound)
+        }
+
+        // Role-based permission control
+        ExecuteMsg::GrantRole { address, role } => execute_grant_role(deps, info, address, role),
+        ExecuteMsg::RevokeRole { address, role } => execute_revoke_role(deps, info, address, role),
     }
 }
 
-pub fn execute_deposit(
-    mut deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+/// Loads the current killswitch status, defaulting to `Normal` for contracts
+/// instantiated before this status was introduced.
+fn current_contract_status(deps: Deps) -> StdResult<ContractStatus> {
+    Ok(CONTRACT_STATUS
+        .may_load(deps.storage)?
+        .map(|info| info.status)
+        .unwrap_or_default())
+}
 
-    // Check if funds were sent
-    if info.funds.is_empty() {
-        return Err(ContractError::NoFunds {});
+/// Loads the full killswitch record, including who/why/when it was last set.
+/// Defaults to `Normal` with no reason for contracts instantiated before this
+/// status was introduced.
+fn current_contract_status_info(deps: Deps) -> StdResult<ContractStatusInfo> {
+    Ok(CONTRACT_STATUS
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| ContractStatusInfo {
+            status: ContractStatus::default(),
+            reason: String::new(),
+            updated_at: Timestamp::from_seconds(0),
+        }))
+}
+
+/// Gates `AddProtocol`/`UpdateProtocol`/`RemoveProtocol`: both `EmergencyOnly`
+/// and `StopAll` freeze protocol management so an operator responding to an
+/// exploited protocol can't have the set of protocols shift under them.
+fn require_protocol_management_allowed(deps: Deps) -> Result<(), ContractError> {
+    match current_contract_status(deps)? {
+        status @ (ContractStatus::EmergencyOnly | ContractStatus::StopAll) => {
+            Err(ContractError::OperationPaused { status })
+        }
+        ContractStatus::Normal | ContractStatus::StopDeposits => Ok(()),
     }
+}
 
-    // Only accept a single denomination per deposit
-    if info.funds.len() > 1 {
-        return Err(ContractError::MultipleDenoms {});
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        DEPOSIT_SWAP_REPLY_ID => handle_deposit_swap_reply(deps, env, msg.result),
+        REBALANCE_LEG_REPLY_ID => handle_rebalance_leg_reply(deps, env, msg.result),
+        HARVEST_REPLY_ID => handle_harvest_reply(deps, env, msg.result),
+        id => Err(ContractError::Std(StdError::generic_err(format!(
+            "Unknown reply id: {}",
+            id
+        )))),
     }
+}
 
-    let deposit_coin = &info.funds[0];
-    let denom = &deposit_coin.denom;
-    let amount = deposit_coin.amount;
+/// Converts an asset amount into the vault shares worth that much, priced
+/// against `total_assets` (the vault's value before the asset amount is
+/// folded in). With no existing share price to measure against — either
+/// the very first deposit, or a withdrawal once the vault has been fully
+/// drained — it falls back to 1:1; otherwise it's `value * total_shares /
+/// total_assets`, rounded down so the caller never receives more shares
+/// than their pro-rata claim.
+fn shares_for_value(value: Uint128, total_shares: Uint128, total_assets: Uint128) -> Uint128 {
+    if total_shares.is_zero() || total_assets.is_zero() {
+        value
+    } else {
+        value.multiply_ratio(total_shares, total_assets)
+    }
+}
 
-    // Check if the denomination is supported
-    if !config.accepted_denoms.contains(&denom.to_string()) {
-        return Err(ContractError::UnsupportedDenom {
-            denom: denom.to_string(),
-        });
+/// Converts a quantity of vault shares into their current USDC claim,
+/// `shares * total_assets / total_shares`, rounded down so a redemption
+/// never pays out more than the pro-rata claim those shares represent.
+fn assets_for_shares(shares: Uint128, total_shares: Uint128, total_assets: Uint128) -> Uint128 {
+    if total_shares.is_zero() {
+        Uint128::zero()
+    } else {
+        shares.multiply_ratio(total_assets, total_shares)
     }
+}
 
-    // Create AstroportRouter instance
-    let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+/// Credits the validated recipient and distributes `usdc_value` to enabled
+/// protocols per their current allocation. Shared by the immediate-credit
+/// paths in `execute_deposit` (same-denom or oracle-valued deposits) and by
+/// `handle_deposit_swap_reply`, which only learns the real `usdc_value`
+/// once the Astroport swap submessage has actually executed.
+///
+/// Rejects with `ZeroValueDeposit` rather than crediting a no-op: a dust
+/// conversion that rounds to zero USDC would otherwise mint zero shares
+/// while still recording a `UserDeposit`, leaving a `UserInfo` entry with
+/// no backing value.
+fn credit_deposit(
+    mut deps: DepsMut,
+    env: Env,
+    recipient: &Addr,
+    denom: &str,
+    original_amount: Uint128,
+    usdc_value: Uint128,
+    timestamp: Timestamp,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if usdc_value.is_zero() {
+        return Err(ContractError::ZeroValueDeposit {});
+    }
 
-    // Convert to USDC if needed
-    let (conversion_msg, usdc_value) = if denom != &config.base_denom {
-        router.safe_convert_to_usdc(deps.as_ref(), denom, amount, risk_parameters.max_slippage)?
-    } else {
-        (
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: env.contract.address.to_string(),
-                amount: vec![Coin {
-                    denom: denom.to_string(),
-                    amount,
-                }],
-            }),
-            amount,
-        )
-    };
+    let total_assets = TOTAL_USDC_VALUE.load(deps.storage)?;
+    let total_shares = TOTAL_SHARES.load(deps.storage)?;
+    let minted_shares = shares_for_value(usdc_value, total_shares, total_assets);
 
-    // Update user's deposit record
-    USER_INFOS.update(
-        deps.storage,
-        &info.sender,
-        |maybe_user_info| -> StdResult<_> {
-            let mut user_info = maybe_user_info.unwrap_or(UserInfo {
-                total_usdc_value: Uint128::zero(),
-                deposits: vec![],
-            });
+    SHARES.update(deps.storage, recipient, |maybe_shares| -> StdResult<_> {
+        Ok(maybe_shares.unwrap_or_default() + minted_shares)
+    })?;
+    TOTAL_SHARES.save(deps.storage, &(total_shares + minted_shares))?;
 
-            // Add the new deposit
-            user_info.deposits.push(UserDeposit {
-                original_token: denom.to_string(),
-                original_amount: amount,
-                usdc_value_at_deposit: usdc_value,
-                timestamp: env.block.time,
-            });
+    USER_INFOS.update(deps.storage, recipient, |maybe_user_info| -> StdResult<_> {
+        let mut user_info = maybe_user_info.unwrap_or(UserInfo {
+            deposits: vec![],
+            cost_basis: Uint128::zero(),
+        });
 
-            // Update total USDC value
-            user_info.total_usdc_value += usdc_value;
+        user_info.deposits.push(UserDeposit {
+            original_token: denom.to_string(),
+            original_amount,
+            usdc_value_at_deposit: usdc_value,
+            timestamp,
+        });
 
-            Ok(user_info)
-        },
-    )?;
+        // Fresh principal carries no gain yet, so the high-water mark rises
+        // in lockstep.
+        user_info.cost_basis += usdc_value;
 
-    // Update total contract value
-    TOTAL_USDC_VALUE.update(deps.storage, |total| -> StdResult<_> {
-        Ok(total + usdc_value)
+        Ok(user_info)
     })?;
 
+    TOTAL_USDC_VALUE.update(deps.storage, |total| -> StdResult<_> { Ok(total + usdc_value) })?;
+
+    tx_log::append_tx(
+        deps.storage,
+        env.block.height,
+        timestamp,
+        recipient.clone(),
+        TxKind::Deposit,
+        usdc_value,
+        format!("{} {}", original_amount, denom),
+    )?;
+
     // Distribute funds to protocols according to current allocations
     let protocol_names: Vec<String> = PROTOCOLS
-        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .keys(deps.storage, None, None, Order::Ascending)
         .map(|key| key.unwrap())
         .collect();
 
     let mut distribution_msgs = vec![];
 
     if !protocol_names.is_empty() {
-        // Get protocol allocations
+        let config = CONFIG.load(deps.storage)?;
         let mut protocol_allocations = HashMap::new();
         for name in &protocol_names {
             if let Some(protocol) = PROTOCOLS.may_load(deps.storage, name)? {
@@ -237,7 +501,6 @@ pub fn execute_deposit(
             }
         }
 
-        // Calculate and execute distribution
         for (name, allocation) in protocol_allocations {
             let protocol_deposit =
                 usdc_value.multiply_ratio(allocation.numerator(), allocation.denominator());
@@ -248,13 +511,28 @@ pub fn execute_deposit(
                     &name,
                     protocol_info.contract_addr.clone(),
                     name.clone(),
+                    protocol_info.deposit_asset.clone(),
+                )?;
+
+                // `protocol_deposit` is a USD value; the adapter expects a
+                // literal quantity of `deposit_asset`, so convert before
+                // handing it to `route_deposit` as a funds amount.
+                let asset_amount = protocol_asset_amount(
+                    deps.as_ref(),
+                    &config,
+                    &env,
+                    &protocol_info.deposit_asset,
+                    protocol_deposit,
                 )?;
 
-                let deposit_msgs =
-                    protocol_adapter.deposit(deps.branch(), env.clone(), protocol_deposit)?;
+                let deposit_msgs = protocol_adapter.deposit(
+                    deps.branch(),
+                    env.clone(),
+                    asset_amount,
+                    Uint128::zero(),
+                )?;
                 distribution_msgs.extend(deposit_msgs);
 
-                // Update protocol balance
                 PROTOCOLS.update(deps.storage, &name, |maybe_protocol| -> StdResult<_> {
                     let mut protocol = maybe_protocol.ok_or_else(|| {
                         StdError::generic_err(format!("Protocol not found: {}", name))
@@ -268,14 +546,437 @@ pub fn execute_deposit(
         }
     }
 
+    Ok(distribution_msgs)
+}
+
+/// Handles the reply from a deposit's Astroport swap submessage: parses the
+/// actual USDC received off the router's `return_amount` event attribute
+/// (the same convention Astroport pools/routers use for swap results) and
+/// only now credits `UserInfo`/`TOTAL_USDC_VALUE`, so the vault books the
+/// amount that genuinely landed rather than the pre-swap simulation.
+fn handle_deposit_swap_reply(
+    mut deps: DepsMut,
+    env: Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_DEPOSIT.load(deps.storage)?;
+    PENDING_DEPOSIT.remove(deps.storage);
+
+    let usdc_value = parse_swap_return_amount(result)?;
+
+    let distribution_msgs = credit_deposit(
+        deps.branch(),
+        env,
+        &pending.recipient,
+        &pending.original_denom,
+        pending.original_amount,
+        usdc_value,
+        pending.timestamp,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(distribution_msgs)
+        .add_attribute("method", "deposit_swap_reply")
+        .add_attribute("depositor", pending.depositor)
+        .add_attribute("recipient", pending.recipient)
+        .add_attribute("original_denom", pending.original_denom)
+        .add_attribute("original_amount", pending.original_amount)
+        .add_attribute("usdc_value", usdc_value))
+}
+
+/// Extracts the `return_amount` attribute Astroport's router/pair contracts
+/// emit on a successful swap from the submessage's `wasm` events.
+fn parse_swap_return_amount(result: SubMsgResult) -> Result<Uint128, ContractError> {
+    let response = result.into_result().map_err(|err| ContractError::ConversionError {
+        error: err,
+    })?;
+
+    response
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .find_map(|event| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "return_amount")
+                .and_then(|attr| attr.value.parse::<u128>().ok())
+        })
+        .map(Uint128::new)
+        .ok_or(ContractError::ConversionError {
+            error: "swap reply missing return_amount event".to_string(),
+        })
+}
+
+/// Reconciles one `Rebalance` leg against `RebalanceInProgress`, consuming
+/// `pending_legs` FIFO in the exact order `StrategyExecutor::execute_rebalance`
+/// dispatched their submessages. Once every leg has replied, commits the
+/// final `PROTOCOLS` balances and allocation percentages via
+/// `StrategyExecutor::finalize_rebalance` instead of leaving them to diverge
+/// from what actually executed.
+fn handle_rebalance_leg_reply(
+    mut deps: DepsMut,
+    env: Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut in_progress = REBALANCE_IN_PROGRESS.load(deps.storage)?;
+    let leg = in_progress.pending_legs.remove(0);
+
+    let mut response = Response::new().add_attribute("method", "rebalance_leg_reply");
+
+    match leg {
+        PendingRebalanceLeg::Direct {
+            protocol_name,
+            kind,
+            planned_amount,
+        } => {
+            // A direct adapter call doesn't echo back a realized amount;
+            // its success is all `reply_on_success` promises, so the
+            // already-validated planned amount is credited as-is.
+            result
+                .into_result()
+                .map_err(|err| ContractError::ConversionError { error: err })?;
+            in_progress.realized.push((protocol_name, kind, planned_amount));
+        }
+        PendingRebalanceLeg::Swapped {
+            protocol_name,
+            kind,
+        } => {
+            let realized_amount = parse_swap_return_amount(result)?;
+            in_progress.realized.push((protocol_name, kind, realized_amount));
+        }
+        PendingRebalanceLeg::AwaitingFundedDeposit {
+            protocol_name,
+            min_out,
+        } => {
+            let funded_amount = parse_swap_return_amount(result)?;
+            if funded_amount < min_out {
+                return Err(ContractError::ExcessiveSlippage {});
+            }
+
+            let protocol_info = PROTOCOLS.load(deps.storage, &protocol_name)?;
+            let protocol_adapter = create_protocol_adapter(
+                &protocol_name,
+                protocol_info.contract_addr.clone(),
+                protocol_name.clone(),
+                protocol_info.deposit_asset.clone(),
+            )?;
+            let deposit_msgs =
+                protocol_adapter.deposit(deps.branch(), env.clone(), funded_amount, min_out)?;
+            response = response.add_messages(deposit_msgs);
+            in_progress
+                .realized
+                .push((protocol_name, RebalanceLegKind::Deposit, funded_amount));
+        }
+    }
+
+    if in_progress.pending_legs.is_empty() {
+        REBALANCE_IN_PROGRESS.remove(deps.storage);
+        let finalize_response = StrategyExecutor::finalize_rebalance(deps, env, in_progress)?;
+        Ok(response
+            .add_attributes(finalize_response.attributes)
+            .add_submessages(finalize_response.messages))
+    } else {
+        REBALANCE_IN_PROGRESS.save(deps.storage, &in_progress)?;
+        Ok(response)
+    }
+}
+
+/// Extracts the `claimed_amount` attribute a protocol's reward-claim call
+/// emits on success from the submessage's `wasm` events, the claim-side
+/// counterpart to `parse_swap_return_amount`'s `return_amount` convention.
+fn parse_claim_return_amount(result: SubMsgResult) -> Result<Uint128, ContractError> {
+    let response = result.into_result().map_err(|err| ContractError::ConversionError {
+        error: err,
+    })?;
+
+    response
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .find_map(|event| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "claimed_amount")
+                .and_then(|attr| attr.value.parse::<u128>().ok())
+        })
+        .map(Uint128::new)
+        .ok_or(ContractError::ConversionError {
+            error: "claim reply missing claimed_amount event".to_string(),
+        })
+}
+
+/// Reconciles one protocol's reward claim against `HarvestInProgress`,
+/// consuming `pending_protocols` FIFO in the order
+/// `StrategyExecutor::harvest_rewards` dispatched their claim submessages.
+/// Once every protocol has replied, hands off to
+/// `StrategyExecutor::finalize_harvest` to commit the realized total (and
+/// optionally compound it).
+fn handle_harvest_reply(
+    deps: DepsMut,
+    env: Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut in_progress = HARVEST_IN_PROGRESS.load(deps.storage)?;
+    let protocol_name = in_progress.pending_protocols.remove(0);
+
+    let realized_amount = parse_claim_return_amount(result)?;
+    in_progress.realized.push((protocol_name, realized_amount));
+
+    let response = Response::new().add_attribute("method", "harvest_reply");
+
+    if in_progress.pending_protocols.is_empty() {
+        let finalize_response = StrategyExecutor::finalize_harvest(deps, env, in_progress)?;
+        Ok(response
+            .add_attributes(finalize_response.attributes)
+            .add_messages(finalize_response.messages))
+    } else {
+        HARVEST_IN_PROGRESS.save(deps.storage, &in_progress)?;
+        Ok(response)
+    }
+}
+
+pub fn execute_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // Check if funds were sent
+    if info.funds.is_empty() {
+        return Err(ContractError::NoFunds {});
+    }
+
+    // Only accept a single denomination per deposit
+    if info.funds.len() > 1 {
+        return Err(ContractError::MultipleDenoms {});
+    }
+
+    let deposit_coin = info.funds[0].clone();
+    process_deposit(
+        deps,
+        env,
+        info.sender,
+        recipient,
+        AssetInfo::Native(deposit_coin.denom),
+        deposit_coin.amount,
+    )
+}
+
+/// Entry point for cw20 deposits: the vault receives tokens via the cw20
+/// contract's `Send`, which calls back in here with a `Cw20ReceiveMsg`.
+/// `info.sender` is the cw20 contract itself (so that's the asset being
+/// deposited), while `wrapper.sender` is the account that actually sent the
+/// tokens - the native-deposit equivalent of `info.sender` there.
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_json(&wrapper.msg)? {
+        Cw20HookMsg::Deposit { recipient } => {
+            let depositor = deps.api.addr_validate(&wrapper.sender)?;
+            process_deposit(
+                deps,
+                env,
+                depositor,
+                recipient,
+                AssetInfo::Cw20(info.sender),
+                wrapper.amount,
+            )
+        }
+    }
+}
+
+/// Shared body of `execute_deposit`/`execute_receive`: once the asset and
+/// amount have been pulled off the native funds or the cw20 receive hook,
+/// both paths value and credit the deposit identically.
+fn process_deposit(
+    mut deps: DepsMut,
+    env: Env,
+    depositor: Addr,
+    recipient: Option<String>,
+    asset: AssetInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let status = current_contract_status(deps.as_ref())?;
+    if status != ContractStatus::Normal {
+        return Err(ContractError::OperationPaused { status });
+    }
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroValueDeposit {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+
+    // Defaults to the sender; validated so shares/value are keyed on a real
+    // account regardless of what the caller passes in.
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => depositor.clone(),
+    };
+
+    // Check if the asset is supported
+    if !config.accepted_denoms.contains(&asset) {
+        return Err(ContractError::UnsupportedDenom { denom: asset.label() });
+    }
+
+    // Create AstroportRouter instance
+    let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+    let label = asset.label();
+    let base_asset = AssetInfo::Native(config.base_denom.clone());
+
+    if asset != base_asset {
+        // Prefer an oracle-backed valuation when a Pyth price feed is
+        // registered for this asset: it's a conservative, deterministic
+        // USD value instead of the router's single-block spot quote, and
+        // the funds are simply booked in place rather than swapped.
+        let oracle_feed = ORACLE_ADDR.may_load(deps.storage)?.and_then(|oracle_addr| {
+            PRICE_FEED_IDS
+                .may_load(deps.storage, label.as_str())
+                .ok()
+                .flatten()
+                .map(|feed_id| (oracle_addr, feed_id))
+        });
+
+        if let Some((oracle_addr, feed_id)) = oracle_feed {
+            let usdc_value = oracle::query_conservative_deposit_value(
+                deps.as_ref(),
+                &oracle_addr,
+                &feed_id,
+                amount,
+                env.block.time,
+                risk_parameters.max_price_staleness,
+            )?;
+
+            // Cross-check the oracle valuation against the router's own
+            // spot quote: if they diverge by more than max_slippage, either
+            // the feed or the pool is being manipulated, so refuse to credit
+            // the deposit rather than trust either one blindly.
+            let router_quote =
+                router.get_price_quote(deps.as_ref(), &asset, &base_asset, amount)?;
+            let divergence = Decimal::from_ratio(
+                usdc_value.abs_diff(router_quote),
+                router_quote.max(Uint128::one()),
+            );
+            if divergence > risk_parameters.max_slippage {
+                return Err(ContractError::OracleDivergence {});
+            }
+
+            // A native asset arrives as attached funds already sitting in
+            // the contract's own balance; a cw20 asset arrives via the
+            // `Send` hook's token transfer, which has already moved the
+            // tokens, so only the native case needs an explicit message.
+            let mut response = Response::new();
+            if let AssetInfo::Native(denom) = &asset {
+                response = response.add_message(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: env.contract.address.to_string(),
+                    amount: vec![Coin { denom: denom.clone(), amount }],
+                }));
+            }
+
+            let timestamp = env.block.time;
+            let distribution_msgs = credit_deposit(
+                deps.branch(),
+                env,
+                &recipient,
+                &label,
+                amount,
+                usdc_value,
+                timestamp,
+            )?;
+
+            return Ok(response
+                .add_messages(distribution_msgs)
+                .add_attribute("method", "deposit")
+                .add_attribute("depositor", depositor)
+                .add_attribute("recipient", recipient)
+                .add_attribute("original_denom", label)
+                .add_attribute("original_amount", amount)
+                .add_attribute("usdc_value", usdc_value));
+        }
+
+        // No oracle feed: swap through Astroport. The real USDC received
+        // is only known once the swap submessage executes, so crediting
+        // the user and distributing to protocols is deferred to `reply`.
+        if PENDING_DEPOSIT.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::DepositInProgress {});
+        }
+
+        let (swap_msgs, expected_out) = router.safe_convert_to_usdc(
+            deps.as_ref(),
+            &asset,
+            amount,
+            risk_parameters.max_slippage,
+        )?;
+
+        PENDING_DEPOSIT.save(
+            deps.storage,
+            &PendingDeposit {
+                depositor: depositor.clone(),
+                recipient: recipient.clone(),
+                original_denom: label.clone(),
+                original_amount: amount,
+                timestamp: env.block.time,
+            },
+        )?;
+
+        // The swap itself is the last message in the sequence (a cw20 input
+        // leg prepends an `IncreaseAllowance`); only it needs to reply so
+        // the actual swap output can be credited.
+        let last_idx = swap_msgs.len() - 1;
+        let submsgs: Vec<SubMsg> = swap_msgs
+            .into_iter()
+            .enumerate()
+            .map(|(i, msg)| {
+                if i == last_idx {
+                    SubMsg::reply_on_success(msg, DEPOSIT_SWAP_REPLY_ID)
+                } else {
+                    SubMsg::new(msg)
+                }
+            })
+            .collect();
+
+        return Ok(Response::new()
+            .add_submessages(submsgs)
+            .add_attribute("method", "deposit")
+            .add_attribute("depositor", depositor)
+            .add_attribute("recipient", recipient)
+            .add_attribute("original_denom", label)
+            .add_attribute("original_amount", amount)
+            .add_attribute("expected_usdc_value", expected_out)
+            .add_attribute("status", "pending_swap"));
+    }
+
+    let conversion_msg = CosmosMsg::Bank(BankMsg::Send {
+        to_address: env.contract.address.to_string(),
+        amount: vec![Coin { denom: label.clone(), amount }],
+    });
+
+    let timestamp = env.block.time;
+    let distribution_msgs = credit_deposit(
+        deps.branch(),
+        env,
+        &recipient,
+        &label,
+        amount,
+        amount,
+        timestamp,
+    )?;
+
     Ok(Response::new()
         .add_message(conversion_msg)
         .add_messages(distribution_msgs)
         .add_attribute("method", "deposit")
-        .add_attribute("depositor", info.sender)
-        .add_attribute("original_denom", denom)
+        .add_attribute("depositor", depositor)
+        .add_attribute("recipient", recipient)
+        .add_attribute("original_denom", label)
         .add_attribute("original_amount", amount)
-        .add_attribute("usdc_value", usdc_value))
+        .add_attribute("usdc_value", amount))
 }
 
 pub fn execute_withdraw(
@@ -284,53 +985,311 @@ pub fn execute_withdraw(
     info: MessageInfo,
     amount: Uint128,
     denom: Option<String>,
+    sync_balances: Option<bool>,
+    exact_output: Option<bool>,
 ) -> Result<Response, ContractError> {
+    let status = current_contract_status(deps.as_ref())?;
+    if status == ContractStatus::StopAll {
+        return Err(ContractError::OperationPaused { status });
+    }
+
     let config = CONFIG.load(deps.storage)?;
-    let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
 
     if amount.is_zero() {
         return Err(ContractError::InvalidAmount {});
     }
 
-    // Get user's current balance
-    let user_info = USER_INFOS
-        .may_load(deps.storage, &info.sender)?
-        .unwrap_or(UserInfo {
-            total_usdc_value: Uint128::zero(),
-            deposits: vec![],
-        });
+    // Refresh `ProtocolInfo.current_balance`/`TOTAL_USDC_VALUE` from live
+    // adapter queries before pricing the withdrawal, so the share-to-asset
+    // conversion and the proportional protocol split both use real
+    // positions rather than whatever bookkeeping the last deposit/rebalance
+    // left behind.
+    if sync_balances.unwrap_or(false) {
+        sync_protocol_balances(deps.branch(), &env)?;
+    }
+
+    let withdraw_denom = denom.unwrap_or(config.base_denom.clone());
+
+    // `exact_output` reinterprets `amount` as the exact quantity of
+    // `withdraw_denom` to receive instead of a USDC redemption value;
+    // translate it to the USDC that costs via the registered pair's
+    // `ReverseSimulation` so the rest of the withdrawal (share burn,
+    // performance fee, protocol split, final conversion) keeps running in
+    // USDC terms unchanged. A no-op for base_denom, where `amount` is
+    // already exact.
+    let amount = if exact_output.unwrap_or(false) && withdraw_denom != config.base_denom {
+        let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+        let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+        let payout_asset = resolve_asset(&config, &withdraw_denom);
+        let (_, required_usdc) = router.convert_from_usdc_exact_out(
+            deps.as_ref(),
+            &payout_asset,
+            amount,
+            risk_parameters.max_slippage,
+        )?;
+        required_usdc
+    } else {
+        amount
+    };
+
+    // `amount` is denominated in assets; convert it to the shares it
+    // represents at the current share price so a partial withdrawal burns
+    // a proportional slice of the user's position rather than a fixed
+    // nominal balance.
+    let total_assets = TOTAL_USDC_VALUE.load(deps.storage)?;
+    let total_shares = TOTAL_SHARES.load(deps.storage)?;
+    let user_shares = SHARES.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    let user_asset_value = assets_for_shares(user_shares, total_shares, total_assets);
+
+    let shares_to_burn = shares_for_value(amount, total_shares, total_assets);
 
     // Check if user has enough funds
-    if user_info.total_usdc_value < amount {
+    if shares_to_burn.is_zero() || shares_to_burn > user_shares {
         return Err(ContractError::InsufficientFunds {});
     }
 
-    // Determine output denomination
-    let withdraw_denom = denom.unwrap_or(config.base_denom.clone());
+    // Redeeming rounds down too, so `assets_out` can never exceed the
+    // pro-rata claim the burned shares represent, even after two roundings.
+    let assets_out = assets_for_shares(shares_to_burn, total_shares, total_assets);
+
+    let user_info = load_user_info(deps.as_ref(), &info.sender)?;
+
+    // Crystallize the performance fee on the withdrawn share of whatever
+    // gain sits above the high-water mark before the withdrawal amount leaves the user's
+    // balance, so the same gain is never charged twice.
+    let fee_amount = performance_fee_due(&config, &user_info, user_asset_value, assets_out);
+    let net_amount = assets_out - fee_amount;
+
+    // Burn the shares and update the vault's asset total
+    SHARES.save(deps.storage, &info.sender, &(user_shares - shares_to_burn))?;
+    TOTAL_SHARES.save(deps.storage, &(total_shares - shares_to_burn))?;
+    TOTAL_USDC_VALUE.save(deps.storage, &(total_assets - assets_out))?;
 
-    // Update user balance before withdrawal
     USER_INFOS.update(
         deps.storage,
         &info.sender,
         |maybe_user_info| -> StdResult<_> {
             let mut user_info = maybe_user_info.unwrap_or(UserInfo {
-                total_usdc_value: Uint128::zero(),
                 deposits: vec![],
+                cost_basis: Uint128::zero(),
             });
 
-            user_info.total_usdc_value -= amount;
+            // Scale cost_basis down by the same fraction of value that just
+            // left, rather than collapsing it to the full remaining value:
+            // only the withdrawn share of the unrealized gain was actually
+            // crystallized above (capped at `assets_out`), so the rest must
+            // stay taxable on the remaining position. Collapsing it outright
+            // would let a withdrawal smaller than the gain (e.g. a dust
+            // amount) fold the entire untaxed remainder into a fresh
+            // high-water mark, dodging the fee on everything left.
+            user_info.cost_basis = if user_asset_value.is_zero() {
+                Uint128::zero()
+            } else {
+                user_info
+                    .cost_basis
+                    .multiply_ratio(user_asset_value - assets_out, user_asset_value)
+            };
 
             Ok(user_info)
         },
     )?;
 
-    // Update total contract value
-    TOTAL_USDC_VALUE.update(deps.storage, |total| -> StdResult<_> { Ok(total - amount) })?;
+    accrue_performance_fee(deps.storage, fee_amount)?;
 
-    // Begin building response with withdrawal messages
-    let mut messages = vec![];
+    tx_log::append_tx(
+        deps.storage,
+        env.block.height,
+        env.block.time,
+        info.sender.clone(),
+        TxKind::Withdraw,
+        net_amount,
+        withdraw_denom.clone(),
+    )?;
 
-    // If protocols have funds, we need to withdraw proportionally from each
+    // With an unbonding period configured, queue a claim instead of paying
+    // out immediately. Nothing is withdrawn from the protocols during the
+    // window: `Claim {}` does that through `build_withdrawal_messages` once
+    // the claim has matured.
+    if let Some(period) = config.unbonding_period.filter(|p| *p > 0) {
+        let release_at = env.block.time.plus_seconds(period);
+
+        CLAIMS.update(deps.storage, &info.sender, |maybe_claims| -> StdResult<_> {
+            let mut claims = maybe_claims.unwrap_or_default();
+            claims.push(Claim {
+                amount: net_amount,
+                denom: withdraw_denom.clone(),
+                release_at,
+            });
+            Ok(claims)
+        })?;
+
+        return Ok(Response::new()
+            .add_attribute("method", "withdraw")
+            .add_attribute("withdrawer", info.sender)
+            .add_attribute("amount", net_amount.to_string())
+            .add_attribute("fee_amount", fee_amount.to_string())
+            .add_attribute("denom", withdraw_denom)
+            .add_attribute("release_at", release_at.to_string()));
+    }
+
+    let messages =
+        build_withdrawal_messages(deps.branch(), env, &info.sender, net_amount, &withdraw_denom)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "withdraw")
+        .add_attribute("withdrawer", info.sender)
+        .add_attribute("amount", net_amount.to_string())
+        .add_attribute("fee_amount", fee_amount.to_string())
+        .add_attribute("denom", withdraw_denom))
+}
+
+/// Computes the performance fee owed on a withdrawal paying out `assets_out`:
+/// `config.performance_fee_bps` applied to the withdrawn share
+/// (`assets_out / user_asset_value`) of whatever gain sits above the user's
+/// high-water mark `cost_basis` (`user_asset_value` being the user's full
+/// share-redemption value before this withdrawal), capped at `assets_out`
+/// so a payout can never go negative.
+fn performance_fee_due(
+    config: &Config,
+    user_info: &UserInfo,
+    user_asset_value: Uint128,
+    assets_out: Uint128,
+) -> Uint128 {
+    if user_asset_value.is_zero() {
+        return Uint128::zero();
+    }
+    let gain = user_asset_value.saturating_sub(user_info.cost_basis);
+    let realized_gain = gain.multiply_ratio(assets_out, user_asset_value);
+
+    realized_gain
+        .multiply_ratio(config.performance_fee_bps as u128, 10_000u128)
+        .min(assets_out)
+}
+
+/// Adds `fee_amount` to the running `ACCRUED_FEES` total. Left unclaimed
+/// (rather than sent immediately) the same way `skim_rebalance_performance_fee`
+/// leaves its own skim - `ClaimFees` is the single place either one actually
+/// pays out, so the two don't race each other with separate `BankMsg`s.
+fn accrue_performance_fee(
+    storage: &mut dyn cosmwasm_std::Storage,
+    fee_amount: Uint128,
+) -> StdResult<()> {
+    if fee_amount.is_zero() {
+        return Ok(());
+    }
+
+    ACCRUED_FEES.update(storage, |total| -> StdResult<_> { Ok(total + fee_amount) })?;
+    Ok(())
+}
+
+/// Resolves a withdrawal label (as stored in `Claim.denom` or passed to
+/// `Withdraw`) back to the `AssetInfo` it names, by matching it against
+/// `config.accepted_denoms`. Falls back to `AssetInfo::Native(label)` so a
+/// `base_denom` withdrawal still resolves even on a config predating
+/// `AssetInfo`-typed `accepted_denoms`.
+fn resolve_asset(config: &Config, label: &str) -> AssetInfo {
+    config
+        .accepted_denoms
+        .iter()
+        .find(|asset| asset.label() == label)
+        .cloned()
+        .unwrap_or_else(|| AssetInfo::Native(label.to_string()))
+}
+
+/// Builds the payout message for `amount` of `asset` to `recipient`: a plain
+/// bank send for a native asset, a `Cw20ExecuteMsg::Transfer` for a cw20 one.
+fn payout_message(asset: &AssetInfo, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match asset {
+        AssetInfo::Native(denom) => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin { denom: denom.clone(), amount }],
+        }),
+        AssetInfo::Cw20(addr) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: addr.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }),
+    })
+}
+
+/// Converts `base_denom_value` (a dollar figure denominated in
+/// `config.base_denom`) into the matching quantity of `deposit_asset`, so a
+/// protocol whose `deposit_asset` isn't the base denom is funded/unwound in
+/// real token terms instead of an adapter call mistaking a USD value for a
+/// token count. A registered Pyth feed prices it directly, on the same
+/// assumption `process_deposit` already relies on for non-base deposits:
+/// the vault books commingled balances in place rather than swapping them,
+/// so the converted amount is assumed to already be sitting in the
+/// contract's own balance. With no oracle feed, falls back to the router's
+/// spot quote. Returns `base_denom_value` unchanged when `deposit_asset` is
+/// already the base denom.
+fn protocol_asset_amount(
+    deps: Deps,
+    config: &Config,
+    env: &Env,
+    deposit_asset: &AssetInfo,
+    base_denom_value: Uint128,
+) -> Result<Uint128, ContractError> {
+    let base_asset = AssetInfo::Native(config.base_denom.clone());
+    if deposit_asset == &base_asset {
+        return Ok(base_denom_value);
+    }
+
+    if let AssetInfo::Native(denom) = deposit_asset {
+        let oracle_feeds = ORACLE_ADDR.may_load(deps.storage)?.and_then(|oracle_addr| {
+            let base_feed = PRICE_FEED_IDS
+                .may_load(deps.storage, config.base_denom.as_str())
+                .ok()
+                .flatten();
+            let target_feed = PRICE_FEED_IDS.may_load(deps.storage, denom.as_str()).ok().flatten();
+            base_feed.zip(target_feed).map(|(b, t)| (oracle_addr, b, t))
+        });
+
+        if let Some((oracle_addr, base_feed, target_feed)) = oracle_feeds {
+            let max_staleness = ORACLE_MAX_STALENESS.load(deps.storage)?;
+            let base_price = oracle::query_validated_price(
+                deps,
+                &oracle_addr,
+                &base_feed,
+                env.block.time,
+                max_staleness,
+            )?;
+            let target_price = oracle::query_validated_price(
+                deps,
+                &oracle_addr,
+                &target_feed,
+                env.block.time,
+                max_staleness,
+            )?;
+            return oracle::convert_amount(base_denom_value, base_price, target_price);
+        }
+    }
+
+    let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+    router.get_price_quote(deps, &base_asset, deposit_asset, base_denom_value)
+}
+
+/// Unwinds `amount` (denominated in `base_denom`) proportionally from each
+/// enabled protocol, converts it to `withdraw_denom` if needed, and sends it
+/// to `recipient`. Shared by the immediate-payout path in `execute_withdraw`
+/// and by `execute_claim` releasing a matured claim.
+fn build_withdrawal_messages(
+    mut deps: DepsMut,
+    env: Env,
+    recipient: &Addr,
+    amount: Uint128,
+    withdraw_denom: &str,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+
+    let mut messages = vec![];
+
+    // If protocols have funds, we need to withdraw proportionally from each
     let protocol_names: Vec<String> = PROTOCOLS
         .keys(deps.storage, None, None, Order::Ascending)
         .map(|key| key.unwrap())
@@ -362,10 +1321,26 @@ pub fn execute_withdraw(
                         &name,
                         protocol_info.contract_addr.clone(),
                         name.clone(),
+                        protocol_info.deposit_asset.clone(),
                     )?;
 
-                    let withdraw_msgs =
-                        protocol_adapter.withdraw(deps.branch(), env.clone(), withdrawal_amount)?;
+                    // `withdrawal_amount` is a USD value; convert it to the
+                    // matching quantity of `deposit_asset` before asking the
+                    // adapter to pull that much out of the protocol.
+                    let asset_amount = protocol_asset_amount(
+                        deps.as_ref(),
+                        &config,
+                        &env,
+                        &protocol_info.deposit_asset,
+                        withdrawal_amount,
+                    )?;
+
+                    let withdraw_msgs = protocol_adapter.withdraw(
+                        deps.branch(),
+                        env.clone(),
+                        asset_amount,
+                        Uint128::zero(),
+                    )?;
                     messages.extend(withdraw_msgs);
 
                     // Update protocol balance
@@ -386,29 +1361,61 @@ pub fn execute_withdraw(
 
     // Convert to requested denom if not base_denom
     if withdraw_denom != config.base_denom {
-        let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+        // Prefer an oracle-backed conversion when both denoms have a
+        // registered Pyth price feed: it's deterministic and immune to the
+        // router's single-block spot pricing.
+        let oracle_feeds = ORACLE_ADDR.may_load(deps.storage)?.and_then(|oracle_addr| {
+            let base_feed = PRICE_FEED_IDS
+                .may_load(deps.storage, config.base_denom.as_str())
+                .ok()
+                .flatten();
+            let target_feed = PRICE_FEED_IDS
+                .may_load(deps.storage, withdraw_denom)
+                .ok()
+                .flatten();
+            base_feed.zip(target_feed).map(|(b, t)| (oracle_addr, b, t))
+        });
 
-        let (conversion_msg, converted_amount) = router.safe_convert_from_usdc(
-            deps.as_ref(),
-            &withdraw_denom,
-            amount,
-            risk_parameters.max_slippage,
-        )?;
+        if let Some((oracle_addr, base_feed, target_feed)) = oracle_feeds {
+            let max_staleness = ORACLE_MAX_STALENESS.load(deps.storage)?;
+            let base_price = oracle::query_validated_price(
+                deps.as_ref(),
+                &oracle_addr,
+                &base_feed,
+                env.block.time,
+                max_staleness,
+            )?;
+            let target_price = oracle::query_validated_price(
+                deps.as_ref(),
+                &oracle_addr,
+                &target_feed,
+                env.block.time,
+                max_staleness,
+            )?;
+            let converted_amount = oracle::convert_amount(amount, base_price, target_price)?;
+            let payout_asset = resolve_asset(&config, withdraw_denom);
+
+            messages.push(payout_message(&payout_asset, recipient, converted_amount)?);
+        } else {
+            let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+            let payout_asset = resolve_asset(&config, withdraw_denom);
+
+            let (conversion_msgs, converted_amount) = router.safe_convert_from_usdc(
+                deps.as_ref(),
+                &payout_asset,
+                amount,
+                risk_parameters.max_slippage,
+            )?;
 
-        messages.push(conversion_msg);
+            messages.extend(conversion_msgs);
 
-        // Send the converted amount to the user
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![Coin {
-                denom: withdraw_denom.clone(),
-                amount: converted_amount,
-            }],
-        }));
+            // Send the converted amount to the user
+            messages.push(payout_message(&payout_asset, recipient, converted_amount)?);
+        }
     } else {
         // Send base_denom directly to the user
         messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
+            to_address: recipient.to_string(),
             amount: vec![Coin {
                 denom: config.base_denom.clone(),
                 amount,
@@ -416,40 +1423,79 @@ pub fn execute_withdraw(
         }));
     }
 
+    Ok(messages)
+}
+
+pub fn execute_claim(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let claims = CLAIMS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+
+    let (matured, pending): (Vec<Claim>, Vec<Claim>) = claims
+        .into_iter()
+        .partition(|claim| claim.release_at <= env.block.time);
+
+    if matured.is_empty() {
+        return Err(ContractError::NoMaturedClaims {});
+    }
+
+    CLAIMS.save(deps.storage, &info.sender, &pending)?;
+
+    let mut messages = vec![];
+    for claim in &matured {
+        messages.extend(build_withdrawal_messages(
+            deps.branch(),
+            env.clone(),
+            &info.sender,
+            claim.amount,
+            &claim.denom,
+        )?);
+    }
+
     Ok(Response::new()
         .add_messages(messages)
-        .add_attribute("method", "withdraw")
-        .add_attribute("withdrawer", info.sender)
-        .add_attribute("amount", amount.to_string())
-        .add_attribute("denom", withdraw_denom))
+        .add_attribute("method", "claim")
+        .add_attribute("claimant", info.sender)
+        .add_attribute("claims_released", matured.len().to_string()))
 }
 
 pub fn execute_emergency_withdraw(
     mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    sync_balances: Option<bool>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
 
-    // Get user's current balance
-    let user_info = USER_INFOS
-        .may_load(deps.storage, &info.sender)?
-        .unwrap_or(UserInfo {
-            total_usdc_value: Uint128::zero(),
-            deposits: vec![],
-        });
+    // Same live-reconciliation option as `Withdraw`: an emergency exit is
+    // exactly when a user most wants their payout based on the protocol's
+    // actual position rather than stale stored balances.
+    if sync_balances.unwrap_or(false) {
+        sync_protocol_balances(deps.branch(), &env)?;
+    }
 
-    if user_info.total_usdc_value.is_zero() {
+    // Emergency exit burns the user's entire share balance, so it always
+    // settles at the position's current redemption value rather than a
+    // stale nominal amount.
+    let user_shares = SHARES.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+
+    if user_shares.is_zero() {
         return Err(ContractError::InsufficientFunds {});
     }
 
+    let total_assets = TOTAL_USDC_VALUE.load(deps.storage)?;
+    let total_shares = TOTAL_SHARES.load(deps.storage)?;
+    let asset_value = assets_for_shares(user_shares, total_shares, total_assets);
+
     // Calculate emergency withdrawal fee
-    let fee_amount = user_info.total_usdc_value.multiply_ratio(
+    let fee_amount = asset_value.multiply_ratio(
         risk_parameters.emergency_withdrawal_fee.numerator(),
         risk_parameters.emergency_withdrawal_fee.denominator(),
     );
-    let withdrawal_amount = user_info.total_usdc_value - fee_amount;
+    let withdrawal_amount = asset_value - fee_amount;
 
     // Withdraw from all protocols
     let mut messages = vec![];
@@ -461,13 +1507,11 @@ pub fn execute_emergency_withdraw(
         .collect();
 
     if !protocol_names.is_empty() {
-        let total_value = TOTAL_USDC_VALUE.load(deps.storage)?;
-
         for name in &protocol_names {
             if let Some(protocol) = PROTOCOLS.may_load(deps.storage, name)? {
                 if protocol.enabled && !protocol.current_balance.is_zero() {
-                    // Calculate proportional withdrawal based on user's share of total
-                    let user_share = Decimal::from_ratio(user_info.total_usdc_value, total_value);
+                    // Calculate proportional withdrawal based on the user's share of total shares
+                    let user_share = Decimal::from_ratio(user_shares, total_shares);
                     let withdrawal_amount = protocol
                         .current_balance
                         .multiply_ratio(user_share.numerator(), user_share.denominator());
@@ -477,12 +1521,14 @@ pub fn execute_emergency_withdraw(
                             &name,
                             protocol.contract_addr.clone(),
                             name.clone(),
+                            protocol.deposit_asset.clone(),
                         )?;
 
                         let withdraw_msgs = protocol_adapter.withdraw(
                             deps.branch(),
                             env.clone(),
                             withdrawal_amount,
+                            Uint128::zero(),
                         )?;
                         messages.extend(withdraw_msgs);
 
@@ -507,27 +1553,27 @@ pub fn execute_emergency_withdraw(
         }
     }
 
-    // Reset user balance
+    // Burn the user's entire share balance and the asset value it redeemed for
+    SHARES.remove(deps.storage, &info.sender);
+    TOTAL_SHARES.save(deps.storage, &(total_shares - user_shares))?;
+    TOTAL_USDC_VALUE.save(deps.storage, &(total_assets - asset_value))?;
+
+    // No shares left means no claim left; reset the high-water mark to match
     USER_INFOS.update(
         deps.storage,
         &info.sender,
         |maybe_user_info| -> StdResult<_> {
             let mut user_info = maybe_user_info.unwrap_or(UserInfo {
-                total_usdc_value: Uint128::zero(),
                 deposits: vec![],
+                cost_basis: Uint128::zero(),
             });
 
-            user_info.total_usdc_value = Uint128::zero();
+            user_info.cost_basis = Uint128::zero();
 
             Ok(user_info)
         },
     )?;
 
-    // Update total contract value
-    TOTAL_USDC_VALUE.update(deps.storage, |total| -> StdResult<_> {
-        Ok(total - user_info.total_usdc_value)
-    })?;
-
     // Send the withdrawal amount to the user
     messages.push(CosmosMsg::Bank(BankMsg::Send {
         to_address: info.sender.to_string(),
@@ -537,6 +1583,21 @@ pub fn execute_emergency_withdraw(
         }],
     }));
 
+    // An emergency exit can move allocations outside what the change
+    // limiters would normally permit; clear their moving averages so the
+    // next legitimate rebalance isn't rejected against a stale reference.
+    limiters::reset_limiter_states(deps.storage)?;
+
+    tx_log::append_tx(
+        deps.storage,
+        env.block.height,
+        env.block.time,
+        info.sender.clone(),
+        TxKind::EmergencyWithdraw,
+        withdrawal_amount,
+        config.base_denom.clone(),
+    )?;
+
     Ok(Response::new()
         .add_messages(messages)
         .add_attribute("method", "emergency_withdraw")
@@ -552,14 +1613,13 @@ pub fn execute_add_protocol(
     name: String,
     contract_addr: String,
     initial_allocation: Decimal,
+    deposit_asset: AssetInfo,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
+    require_protocol_management_allowed(deps.as_ref())?;
+
     let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
 
-    // Only admin can add protocols
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    require_role(deps.as_ref(), &info.sender, Role::ProtocolManager)?;
 
     // Check if protocol already exists
     if PROTOCOLS.may_load(deps.storage, &name)?.is_some() {
@@ -571,13 +1631,37 @@ pub fn execute_add_protocol(
         return Err(ContractError::ExcessiveAllocation {});
     }
 
+    // A native deposit asset must be one the vault actually accepts and can
+    // fund from its own balance; anything else would route funds the
+    // contract never holds. Cw20 assets aren't vault-accounted this way, so
+    // they're exempt from this check.
+    if let AssetInfo::Native(denom) = &deposit_asset {
+        let config = CONFIG.load(deps.storage)?;
+        if !config.accepted_denoms.contains(&deposit_asset) {
+            return Err(ContractError::AssetMismatch {
+                expected: config
+                    .accepted_denoms
+                    .iter()
+                    .map(AssetInfo::label)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                received: denom.clone(),
+            });
+        }
+    }
+
     // Use our conditional validation helper with api directly
     let validated_addr = addr_validate(deps.api, &contract_addr)?;
 
     // Create protocol adapter to validate it works
     // During tests, skip actual protocol adapter creation which would fail with non-supported protocol names
     #[cfg(not(test))]
-    create_protocol_adapter(&name, validated_addr.clone(), name.clone())?;
+    create_protocol_adapter(
+        &name,
+        validated_addr.clone(),
+        name.clone(),
+        deposit_asset.clone(),
+    )?;
 
     // Add protocol to storage
     let protocol_info = ProtocolInfo {
@@ -586,6 +1670,7 @@ pub fn execute_add_protocol(
         allocation_percentage: initial_allocation,
         current_balance: Uint128::zero(),
         enabled: true,
+        deposit_asset,
     };
 
     PROTOCOLS.save(deps.storage, &name, &protocol_info)?;
@@ -636,18 +1721,61 @@ pub fn execute_add_protocol(
         .add_attribute("initial_allocation", initial_allocation.to_string()))
 }
 
+/// Rescales `allocations` (each a fraction of `total`) to basis points that
+/// sum to exactly 10,000 using largest-remainder (Hamilton) apportionment:
+/// every entry's raw share is floored, then the leftover basis points are
+/// handed out one at a time to the entries with the largest fractional
+/// remainder, ties broken by ascending name. Unlike scaling each entry by
+/// `1 / total` and special-casing the last one, this never leaves rounding
+/// dust for the rebalance math downstream to trust as real drift.
+fn normalize_allocations_by_largest_remainder(
+    allocations: &[(String, Decimal)],
+    total: Decimal,
+) -> Vec<(String, Decimal)> {
+    const BASIS_POINTS: u128 = 10_000;
+
+    let total_atomics = total.atomics().u128();
+
+    // (name, floor basis points, remainder numerator out of `total_atomics`)
+    let mut shares: Vec<(String, u128, u128)> = allocations
+        .iter()
+        .map(|(name, allocation)| {
+            let product = allocation.atomics().u128() * BASIS_POINTS;
+            (name.clone(), product / total_atomics, product % total_atomics)
+        })
+        .collect();
+
+    let floor_sum: u128 = shares.iter().map(|(_, floor_bp, _)| floor_bp).sum();
+    let mut shortfall = BASIS_POINTS - floor_sum;
+
+    // Largest remainder first; ascending name breaks ties deterministically.
+    shares.sort_by(|(name_a, _, rem_a), (name_b, _, rem_b)| {
+        rem_b.cmp(rem_a).then(name_a.cmp(name_b))
+    });
+
+    for (_, floor_bp, _) in shares.iter_mut() {
+        if shortfall == 0 {
+            break;
+        }
+        *floor_bp += 1;
+        shortfall -= 1;
+    }
+
+    shares
+        .into_iter()
+        .map(|(name, final_bp, _)| (name, Decimal::from_ratio(final_bp, BASIS_POINTS)))
+        .collect()
+}
+
 pub fn execute_remove_protocol(
     mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     name: String,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
+    require_protocol_management_allowed(deps.as_ref())?;
 
-    // Only admin can remove protocols
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    require_role(deps.as_ref(), &info.sender, Role::ProtocolManager)?;
 
     // Check if protocol exists
     let protocol = PROTOCOLS
@@ -660,11 +1788,19 @@ pub fn execute_remove_protocol(
     if !protocol.current_balance.is_zero() {
         #[cfg(not(test))]
         {
-            let protocol_adapter =
-                create_protocol_adapter(&name, protocol.contract_addr.clone(), name.clone())?;
-
-            let withdraw_msgs =
-                protocol_adapter.withdraw(deps.branch(), _env.clone(), protocol.current_balance)?;
+            let protocol_adapter = create_protocol_adapter(
+                &name,
+                protocol.contract_addr.clone(),
+                name.clone(),
+                protocol.deposit_asset.clone(),
+            )?;
+
+            let withdraw_msgs = protocol_adapter.withdraw(
+                deps.branch(),
+                _env.clone(),
+                protocol.current_balance,
+                Uint128::zero(),
+            )?;
             messages.extend(withdraw_msgs);
         }
     }
@@ -691,27 +1827,21 @@ pub fn execute_remove_protocol(
 
         // Redistribute removed allocation proportionally
         if !remaining_total_allocation.is_zero() && !protocol_names.is_empty() {
+            let mut allocations: Vec<(String, Decimal)> = Vec::with_capacity(protocol_names.len());
             for protocol_name in &protocol_names {
-                PROTOCOLS.update(deps.storage, protocol_name, |proto_opt| -> StdResult<_> {
-                    let mut protocol = proto_opt.unwrap();
+                let protocol = PROTOCOLS.load(deps.storage, protocol_name)?;
+                allocations.push((protocol_name.clone(), protocol.allocation_percentage));
+            }
 
-                    // Scale up remaining allocations proportionally
-                    if remaining_total_allocation.is_zero() {
-                        protocol.allocation_percentage = old_allocation
-                            / Decimal::from_ratio(protocol_names.len() as u128, 1u128);
-                    } else {
-                        // Calculate new allocation and ensure precision issues don't cause problems
-                        let new_allocation = protocol.allocation_percentage * Decimal::one()
-                            / remaining_total_allocation;
-
-                        // When redistributing the last protocol, ensure we get a perfect 100%
-                        if protocol_names.len() == 1 {
-                            protocol.allocation_percentage = Decimal::one();
-                        } else {
-                            protocol.allocation_percentage = new_allocation;
-                        }
-                    }
+            let normalized = normalize_allocations_by_largest_remainder(
+                &allocations,
+                remaining_total_allocation,
+            );
 
+            for (protocol_name, new_allocation) in normalized {
+                PROTOCOLS.update(deps.storage, &protocol_name, |proto_opt| -> StdResult<_> {
+                    let mut protocol = proto_opt.unwrap();
+                    protocol.allocation_percentage = new_allocation;
                     Ok(protocol)
                 })?;
             }
@@ -732,12 +1862,9 @@ pub fn execute_update_protocol(
     enabled: Option<bool>,
     contract_addr: Option<String>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
+    require_protocol_management_allowed(deps.as_ref())?;
 
-    // Only admin can update protocols
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    require_role(deps.as_ref(), &info.sender, Role::ProtocolManager)?;
 
     // Store a reference to the API to avoid borrowing deps inside the closure
     let api = deps.api;
@@ -771,18 +1898,36 @@ pub fn execute_update_protocol(
 }
 
 pub fn execute_rebalance(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     target_allocations: Vec<(String, Decimal)>,
     reason: String,
+    sync_balances: Option<bool>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
+    // `StopDeposits`/`EmergencyOnly`/`StopAll` all pause rebalancing; only
+    // `Normal` lets the AI operator move allocations around.
+    let status = current_contract_status(deps.as_ref())?;
+    if status != ContractStatus::Normal {
+        return Err(ContractError::OperationPaused { status });
+    }
+
     let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
 
-    // Verify sender is AI operator
-    if info.sender != config.ai_operator {
-        return Err(ContractError::Unauthorized {});
+    require_role(deps.as_ref(), &info.sender, Role::Rebalancer)?;
+
+    skim_rebalance_performance_fee(deps.branch(), &risk_parameters)?;
+
+    refresh_and_check_twap_deviation(deps.branch(), &env, &risk_parameters)?;
+
+    // Refresh `ProtocolInfo.current_balance`/`TOTAL_USDC_VALUE` from live
+    // adapter queries before sizing moves, so a protocol that's accrued
+    // yield (or drifted via an exchange-rate change) since the last sync
+    // doesn't throw off how much is withdrawn or deposited; see
+    // `StrategyExecutor::calculate_rebalance_actions`'s `use_live_allocation`.
+    let use_live_allocation = sync_balances.unwrap_or(false);
+    if use_live_allocation {
+        sync_protocol_balances(deps.branch(), &env)?;
     }
 
     // Execute rebalance using the StrategyExecutor
@@ -793,194 +1938,1160 @@ pub fn execute_rebalance(
         target_allocations,
         reason,
         risk_parameters.max_allocation_per_protocol,
+        risk_parameters.max_slippage,
+        risk_parameters.max_slippage_bps,
+        use_live_allocation,
     )
 }
 
-pub fn execute_update_risk_parameters(
+/// Claims every enabled protocol's pending rewards and, if `compound` is
+/// set, redeposits the harvested total back into protocols per their
+/// current allocation. Gated the same way `Rebalance` is: only `Normal`
+/// status and `Role::Rebalancer`, since harvesting moves real funds
+/// in/out of protocol adapters just like a rebalance leg does.
+pub fn execute_harvest_rewards(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    risk_parameters: RiskParametersMsg,
+    compound: Option<bool>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-
-    // Only admin can update risk parameters
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    let status = current_contract_status(deps.as_ref())?;
+    if status != ContractStatus::Normal {
+        return Err(ContractError::OperationPaused { status });
     }
 
-    // Update risk parameters
-    let updated_parameters = RiskParameters {
-        max_allocation_per_protocol: risk_parameters.max_allocation_per_protocol,
-        max_slippage: risk_parameters.max_slippage,
-        rebalance_threshold: risk_parameters.rebalance_threshold,
-        emergency_withdrawal_fee: risk_parameters.emergency_withdrawal_fee,
-    };
-
-    RISK_PARAMETERS.save(deps.storage, &updated_parameters)?;
+    require_role(deps.as_ref(), &info.sender, Role::Rebalancer)?;
 
-    Ok(Response::new()
-        .add_attribute("method", "update_risk_parameters")
-        .add_attribute(
-            "max_allocation_per_protocol",
-            updated_parameters.max_allocation_per_protocol.to_string(),
-        )
-        .add_attribute("max_slippage", updated_parameters.max_slippage.to_string())
-        .add_attribute(
-            "rebalance_threshold",
-            updated_parameters.rebalance_threshold.to_string(),
-        )
-        .add_attribute(
-            "emergency_withdrawal_fee",
-            updated_parameters.emergency_withdrawal_fee.to_string(),
-        ))
+    StrategyExecutor::harvest_rewards(deps, env, info, compound.unwrap_or(false))
 }
 
-pub fn execute_add_supported_token(
+/// Skims the vault-wide performance fee on every `Rebalance`: any growth in
+/// `TOTAL_USDC_VALUE` since `VAULT_HIGH_WATER_MARK` is realized yield, taxed
+/// at `RiskParameters.performance_fee` and added to `ACCRUED_FEES` (see
+/// `accrue_performance_fee`) before the high-water mark is raised to the
+/// post-fee total. The first call after instantiation just seeds the
+/// high-water mark at the current total rather than taxing it, so existing
+/// principal is never charged.
+fn skim_rebalance_performance_fee(
     deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    denom: String,
-) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
+    risk_parameters: &RiskParameters,
+) -> Result<(), ContractError> {
+    let total_assets = TOTAL_USDC_VALUE.load(deps.storage)?;
+
+    let high_water_mark = match VAULT_HIGH_WATER_MARK.may_load(deps.storage)? {
+        Some(hwm) => hwm,
+        None => {
+            VAULT_HIGH_WATER_MARK.save(deps.storage, &total_assets)?;
+            return Ok(());
+        }
+    };
 
-    // Only admin can add supported tokens
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
+    let gain = total_assets.saturating_sub(high_water_mark);
+    if gain.is_zero() {
+        return Ok(());
     }
 
-    // Check if token is already supported
-    if config.accepted_denoms.contains(&denom) {
-        return Ok(Response::new()
-            .add_attribute("method", "add_supported_token")
-            .add_attribute("denom", denom)
-            .add_attribute("status", "already_supported"));
+    let fee_amount = gain.multiply_ratio(
+        risk_parameters.performance_fee.numerator(),
+        risk_parameters.performance_fee.denominator(),
+    );
+    if fee_amount.is_zero() {
+        return Ok(());
     }
 
-    // Add the token to supported list
-    config.accepted_denoms.push(denom.clone());
-    CONFIG.save(deps.storage, &config)?;
+    accrue_performance_fee(deps.storage, fee_amount)?;
 
-    Ok(Response::new()
-        .add_attribute("method", "add_supported_token")
-        .add_attribute("denom", denom))
+    let total_after_fee = total_assets - fee_amount;
+    TOTAL_USDC_VALUE.save(deps.storage, &total_after_fee)?;
+    VAULT_HIGH_WATER_MARK.save(deps.storage, &total_after_fee)?;
+
+    Ok(())
 }
 
-pub fn execute_remove_supported_token(
+/// Updates the TWAP accumulator for every non-base-denom protocol deposit
+/// asset with a fresh Astroport spot quote, then rejects the rebalance with
+/// `PriceDeviationTooHigh` if that spot quote has drifted from the TWAP by
+/// more than `RiskParameters.max_price_deviation`. Skips the check entirely
+/// for a denom that hasn't accumulated `TWAP_DEVIATION_WINDOW_SECS` of
+/// history yet, per `twap::twap_since_genesis`.
+fn refresh_and_check_twap_deviation(
     deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    denom: String,
-) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
+    env: &Env,
+    risk_parameters: &RiskParameters,
+) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+    let base_asset = AssetInfo::Native(config.base_denom.clone());
 
-    // Only admin can remove supported tokens
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    let protocol_names: Vec<String> = PROTOCOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
 
-    // Can't remove base denom
-    if denom == config.base_denom {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Cannot remove base denomination",
-        )));
-    }
+    for name in protocol_names {
+        let protocol = PROTOCOLS.load(deps.storage, &name)?;
+        let AssetInfo::Native(denom) = &protocol.deposit_asset else {
+            continue;
+        };
+        if denom == &config.base_denom {
+            continue;
+        }
 
-    // Check if token is supported
-    if !config.accepted_denoms.contains(&denom) {
-        return Ok(Response::new()
-            .add_attribute("method", "remove_supported_token")
-            .add_attribute("denom", denom)
-            .add_attribute("status", "not_supported"));
+        // One unit of the paired asset, quoted in `base_denom`, is the spot
+        // "price" this denom's TWAP tracks.
+        let spot_quote = router.get_price_quote(
+            deps.as_ref(),
+            &protocol.deposit_asset,
+            &base_asset,
+            Uint128::new(1_000_000),
+        )?;
+        let spot_price = Decimal::from_ratio(spot_quote, 1_000_000u128);
+
+        let state = twap::update_twap(deps.storage, denom, spot_price, env.block.time)?;
+        let twap_price = twap::twap_since_genesis(
+            deps.storage,
+            denom,
+            TWAP_DEVIATION_WINDOW_SECS,
+            env.block.time,
+        )?;
+
+        if let Some(twap_price) = twap_price {
+            twap::check_price_deviation(
+                state.last_price,
+                twap_price,
+                risk_parameters.max_price_deviation,
+            )?;
+        }
     }
 
-    // Remove the token from supported list
-    config.accepted_denoms.retain(|d| d != &denom);
-    CONFIG.save(deps.storage, &config)?;
+    Ok(())
+}
 
-    Ok(Response::new()
-        .add_attribute("method", "remove_supported_token")
-        .add_attribute("denom", denom))
+/// Validates that `recipients`' weights sum to exactly `Decimal::one()` and
+/// every address is well-formed, shared by `execute_set_fee_recipients` and
+/// `execute_update_fee_config` so the two can't drift on what counts as a
+/// valid split.
+fn validate_fee_recipients(
+    deps: Deps,
+    recipients: Vec<(String, Decimal)>,
+) -> Result<Vec<(Addr, Decimal)>, ContractError> {
+    let total_weight: Decimal = recipients.iter().map(|(_, weight)| *weight).sum();
+    if total_weight != Decimal::one() {
+        return Err(ContractError::InvalidAllocations {});
+    }
+
+    Ok(recipients
+        .into_iter()
+        .map(|(addr, weight)| Ok((addr_validate(deps.api, &addr)?, weight)))
+        .collect::<StdResult<Vec<_>>>()?)
 }
 
-pub fn execute_update_admin(
+pub fn execute_set_fee_recipients(
     deps: DepsMut,
-    _env: Env,
     info: MessageInfo,
-    admin: String,
+    recipients: Vec<(String, Decimal)>,
 ) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
 
-    // Only current admin can update admin
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
-
-    // Validate and update admin address
-    let validated_admin = deps.api.addr_validate(&admin)?;
-    config.admin = validated_admin;
-    CONFIG.save(deps.storage, &config)?;
+    let validated = validate_fee_recipients(deps.as_ref(), recipients)?;
+    FEE_RECIPIENTS.save(deps.storage, &validated)?;
 
     Ok(Response::new()
-        .add_attribute("method", "update_admin")
-        .add_attribute("new_admin", admin))
+        .add_attribute("method", "set_fee_recipients")
+        .add_attribute("recipient_count", validated.len().to_string()))
 }
 
-pub fn execute_update_ai_operator(
+/// Combines `SetFeeRecipients`'s split update with `UpdateRiskParameters`'s
+/// `performance_fee` so both change in the same transaction.
+pub fn execute_update_fee_config(
     deps: DepsMut,
-    _env: Env,
     info: MessageInfo,
-    ai_operator: String,
+    performance_fee: Decimal,
+    recipients: Vec<(String, Decimal)>,
 ) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
 
-    // Only admin can update AI operator
-    if info.sender != config.admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    let validated = validate_fee_recipients(deps.as_ref(), recipients)?;
 
-    // Validate and update AI operator address
-    let validated_operator = deps.api.addr_validate(&ai_operator)?;
-    config.ai_operator = validated_operator;
-    CONFIG.save(deps.storage, &config)?;
+    RISK_PARAMETERS.update(deps.storage, |mut risk_parameters| -> StdResult<_> {
+        risk_parameters.performance_fee = performance_fee;
+        Ok(risk_parameters)
+    })?;
+    FEE_RECIPIENTS.save(deps.storage, &validated)?;
 
     Ok(Response::new()
-        .add_attribute("method", "update_ai_operator")
-        .add_attribute("new_ai_operator", ai_operator))
+        .add_attribute("method", "update_fee_config")
+        .add_attribute("performance_fee", performance_fee.to_string())
+        .add_attribute("recipient_count", validated.len().to_string()))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetUserInfo { address } => to_json_binary(&query_user_info(deps, address)?),
-        QueryMsg::GetProtocols {} => to_json_binary(&query_protocols(deps)?),
-        QueryMsg::GetProtocolInfo { name } => to_json_binary(&query_protocol_info(deps, name)?),
-        QueryMsg::GetRiskParameters {} => to_json_binary(&query_risk_parameters(deps)?),
-        QueryMsg::GetRebalanceHistory { limit } => {
-            to_json_binary(&query_rebalance_history(deps, limit)?)
-        }
-        QueryMsg::GetTotalValue {} => to_json_binary(&query_total_value(deps)?),
-        QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+/// Pays out the `ACCRUED_FEES` pot built up by `accrue_performance_fee`
+/// (both the per-withdrawal performance fee and the vault-wide rebalance
+/// skim feed the same pot) across `FEE_RECIPIENTS`' weights, falling back to
+/// `config.fee_collector` if no split has ever been registered so fees never
+/// get stranded. Resets the pot to zero once the messages are built.
+pub fn execute_claim_fees(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let total_fees = ACCRUED_FEES.load(deps.storage)?;
+    if total_fees.is_zero() {
+        return Err(ContractError::NoFeesToClaim {});
     }
-}
 
-fn query_user_info(deps: Deps, address: String) -> StdResult<GetUserInfoResponse> {
-    // In tests, skip validation
-    #[cfg(test)]
-    let addr = Addr::unchecked(&address);
+    let config = CONFIG.load(deps.storage)?;
+    let recipients = FEE_RECIPIENTS.may_load(deps.storage)?.unwrap_or_default();
 
-    // In production, validate the address
-    #[cfg(not(test))]
-    let addr = deps.api.addr_validate(&address)?;
+    let messages = if recipients.is_empty() {
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            to_address: config.fee_collector.to_string(),
+            amount: vec![Coin {
+                denom: config.base_denom.clone(),
+                amount: total_fees,
+            }],
+        })]
+    } else {
+        recipients
+            .iter()
+            .filter_map(|(recipient, weight)| {
+                let share = total_fees.multiply_ratio(weight.numerator(), weight.denominator());
+                if share.is_zero() {
+                    return None;
+                }
+                Some(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: recipient.to_string(),
+                    amount: vec![Coin {
+                        denom: config.base_denom.clone(),
+                        amount: share,
+                    }],
+                }))
+            })
+            .collect()
+    };
+
+    ACCRUED_FEES.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "claim_fees")
+        .add_attribute("amount", total_fees.to_string()))
+}
+
+/// Compares each enabled protocol's live weight (`current_balance /
+/// TOTAL_USDC_VALUE`) against its stored `allocation_percentage` and flags
+/// only those whose absolute drift exceeds `RiskParameters.rebalance_threshold`.
+/// Withdrawal moves are ordered before deposit moves so `execute_auto_rebalance`
+/// frees funds before redeploying them. Returns the moves plus a reason string
+/// summarizing which protocols triggered them.
+fn compute_rebalance_plan(deps: Deps) -> StdResult<(Vec<RebalancePlanMove>, String)> {
+    let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+    let total_value = TOTAL_USDC_VALUE.load(deps.storage)?;
+
+    let protocol_names: Vec<String> = PROTOCOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
+
+    let mut moves = vec![];
+    let mut triggers = vec![];
+
+    for name in protocol_names {
+        let protocol = PROTOCOLS.load(deps.storage, &name)?;
+        if !protocol.enabled {
+            continue;
+        }
+
+        let current_weight = if total_value.is_zero() {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(protocol.current_balance, total_value)
+        };
+        let target_weight = protocol
+            .allocation_percentage
+            .min(risk_parameters.max_allocation_per_protocol);
+
+        let drift = if current_weight > target_weight {
+            current_weight - target_weight
+        } else {
+            target_weight - current_weight
+        };
+
+        if drift <= risk_parameters.rebalance_threshold {
+            continue;
+        }
+
+        let target_balance =
+            total_value.multiply_ratio(target_weight.numerator(), target_weight.denominator());
+        let (withdraw_amount, deposit_amount) = if protocol.current_balance > target_balance {
+            (protocol.current_balance - target_balance, Uint128::zero())
+        } else {
+            (Uint128::zero(), target_balance - protocol.current_balance)
+        };
+
+        triggers.push(format!("{} drifted {}", name, drift));
+        moves.push(RebalancePlanMove {
+            protocol: name,
+            current_weight,
+            target_weight,
+            drift,
+            withdraw_amount,
+            deposit_amount,
+        });
+    }
+
+    // Stable sort: withdrawals (non-zero withdraw_amount) first, deposits after.
+    moves.sort_by_key(|m| m.withdraw_amount.is_zero());
+
+    let reason = if triggers.is_empty() {
+        "no protocol exceeds rebalance_threshold".to_string()
+    } else {
+        format!("rebalance_threshold exceeded for: {}", triggers.join(", "))
+    };
+
+    Ok((moves, reason))
+}
+
+/// Previews the withdrawals/deposits `StrategyExecutor::execute_rebalance`
+/// would send for `target_allocations`, reusing the same
+/// `calculate_rebalance_actions` the real execute path uses so the preview
+/// can never drift from what actually runs. Legs against an
+/// `astroport_amm` protocol are quoted via its local constant-product
+/// swap math; other protocols don't swap, so both fields come back `None`.
+fn query_simulate_rebalance(
+    deps: Deps,
+    env: Env,
+    target_allocations: Vec<(String, Decimal)>,
+) -> StdResult<GetRebalanceSimulationResponse> {
+    let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+    let total_value = TOTAL_USDC_VALUE.load(deps.storage)?;
+
+    let protocol_names: Vec<String> = PROTOCOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
+
+    let mut current_protocols = vec![];
+    for name in protocol_names {
+        if let Some(protocol) = PROTOCOLS.may_load(deps.storage, &name)? {
+            current_protocols.push(protocol);
+        }
+    }
+
+    let actions = StrategyExecutor::calculate_rebalance_actions(
+        deps,
+        current_protocols,
+        &target_allocations,
+        total_value,
+        risk_parameters.max_slippage_bps,
+        false,
+    )?;
+
+    let mut legs = simulate_rebalance_legs(
+        deps,
+        &env,
+        &actions.withdrawals,
+        "withdraw",
+        &risk_parameters,
+    )?;
+    legs.extend(simulate_rebalance_legs(
+        deps,
+        &env,
+        &actions.deposits,
+        "deposit",
+        &risk_parameters,
+    )?);
+
+    Ok(GetRebalanceSimulationResponse { legs })
+}
+
+/// Quotes one side (withdrawals or deposits) of a `SimulateRebalance`
+/// preview. `action` is "withdraw" or "deposit", matching which
+/// `AstroportAmmAdapter` preview method applies.
+fn simulate_rebalance_legs(
+    deps: Deps,
+    env: &Env,
+    actions: &[RebalanceAction],
+    action: &str,
+    risk_parameters: &RiskParameters,
+) -> StdResult<Vec<RebalanceSimulationLeg>> {
+    actions
+        .iter()
+        .map(|a| {
+            let adapter = create_protocol_adapter(
+                &a.protocol_name,
+                a.contract_addr.clone(),
+                a.protocol_name.clone(),
+                a.deposit_asset.clone(),
+            )?;
+
+            let (expected_out, min_receive) = if adapter.protocol_type() == "astroport_amm" {
+                let amm = AstroportAmmAdapter {
+                    contract_addr: a.contract_addr.clone(),
+                    name: a.protocol_name.clone(),
+                };
+                let (expected_out, min_receive) = if action == "deposit" {
+                    amm.simulate_deposit(deps, a.amount, risk_parameters.max_slippage)?
+                } else {
+                    amm.simulate_withdraw(deps, env, a.amount, risk_parameters.max_slippage)?
+                };
+                (Some(expected_out), Some(min_receive))
+            } else {
+                (None, None)
+            };
+
+            Ok(RebalanceSimulationLeg {
+                protocol: a.protocol_name.clone(),
+                action: action.to_string(),
+                amount: a.amount,
+                expected_out,
+                min_receive,
+            })
+        })
+        .collect()
+}
+
+/// Executes whatever `compute_rebalance_plan` currently computes: pulls every
+/// drifted protocol back toward its stored `allocation_percentage` and logs
+/// the move to the transaction ledger. A no-op (no ledger entry) when nothing
+/// has drifted past `rebalance_threshold`.
+pub fn execute_auto_rebalance(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let status = current_contract_status(deps.as_ref())?;
+    if status != ContractStatus::Normal {
+        return Err(ContractError::OperationPaused { status });
+    }
+
+    require_role(deps.as_ref(), &info.sender, Role::Rebalancer)?;
+
+    let (moves, reason) = compute_rebalance_plan(deps.as_ref())?;
+
+    if moves.is_empty() {
+        return Ok(Response::new()
+            .add_attribute("method", "auto_rebalance")
+            .add_attribute("rebalance_needed", "false"));
+    }
+
+    let mut messages = vec![];
+    for mv in &moves {
+        if mv.withdraw_amount.is_zero() {
+            continue;
+        }
+
+        let protocol = PROTOCOLS.load(deps.storage, &mv.protocol)?;
+        let adapter = create_protocol_adapter(
+            &mv.protocol,
+            protocol.contract_addr.clone(),
+            mv.protocol.clone(),
+            protocol.deposit_asset.clone(),
+        )?;
+        messages.extend(adapter.withdraw(
+            deps.branch(),
+            env.clone(),
+            mv.withdraw_amount,
+            Uint128::zero(),
+        )?);
+
+        PROTOCOLS.update(deps.storage, &mv.protocol, |protocol| -> StdResult<_> {
+            let mut protocol = protocol.ok_or_else(|| StdError::not_found("ProtocolInfo"))?;
+            protocol.current_balance = protocol.current_balance.saturating_sub(mv.withdraw_amount);
+            Ok(protocol)
+        })?;
+    }
+
+    for mv in &moves {
+        if mv.deposit_amount.is_zero() {
+            continue;
+        }
+
+        let protocol = PROTOCOLS.load(deps.storage, &mv.protocol)?;
+        let adapter = create_protocol_adapter(
+            &mv.protocol,
+            protocol.contract_addr.clone(),
+            mv.protocol.clone(),
+            protocol.deposit_asset.clone(),
+        )?;
+        messages.extend(adapter.deposit(
+            deps.branch(),
+            env.clone(),
+            mv.deposit_amount,
+            Uint128::zero(),
+        )?);
+
+        PROTOCOLS.update(deps.storage, &mv.protocol, |protocol| -> StdResult<_> {
+            let mut protocol = protocol.ok_or_else(|| StdError::not_found("ProtocolInfo"))?;
+            protocol.current_balance += mv.deposit_amount;
+            Ok(protocol)
+        })?;
+    }
+
+    let total_moved = moves
+        .iter()
+        .fold(Uint128::zero(), |total, m| total + m.withdraw_amount + m.deposit_amount);
+
+    tx_log::append_tx(
+        deps.storage,
+        env.block.height,
+        env.block.time,
+        info.sender,
+        TxKind::Rebalance,
+        total_moved,
+        reason.clone(),
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "auto_rebalance")
+        .add_attribute("reason", reason))
+}
+
+pub fn execute_update_risk_parameters(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    risk_parameters: RiskParametersMsg,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    validate_max_slippage(risk_parameters.max_slippage_bps)?;
+
+    // Update risk parameters
+    let updated_parameters = RiskParameters {
+        max_allocation_per_protocol: risk_parameters.max_allocation_per_protocol,
+        max_slippage: risk_parameters.max_slippage,
+        rebalance_threshold: risk_parameters.rebalance_threshold,
+        emergency_withdrawal_fee: risk_parameters.emergency_withdrawal_fee,
+        max_price_staleness: risk_parameters.max_price_staleness,
+        performance_fee: risk_parameters.performance_fee,
+        max_price_deviation: risk_parameters.max_price_deviation,
+        max_slippage_bps: risk_parameters.max_slippage_bps,
+    };
+
+    RISK_PARAMETERS.save(deps.storage, &updated_parameters)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_risk_parameters")
+        .add_attribute(
+            "max_allocation_per_protocol",
+            updated_parameters.max_allocation_per_protocol.to_string(),
+        )
+        .add_attribute("max_slippage", updated_parameters.max_slippage.to_string())
+        .add_attribute(
+            "rebalance_threshold",
+            updated_parameters.rebalance_threshold.to_string(),
+        )
+        .add_attribute(
+            "emergency_withdrawal_fee",
+            updated_parameters.emergency_withdrawal_fee.to_string(),
+        )
+        .add_attribute(
+            "performance_fee",
+            updated_parameters.performance_fee.to_string(),
+        )
+        .add_attribute(
+            "max_price_deviation",
+            updated_parameters.max_price_deviation.to_string(),
+        )
+        .add_attribute(
+            "max_slippage_bps",
+            updated_parameters.max_slippage_bps.to_string(),
+        ))
+}
+
+/// Rejects a `max_slippage_bps` outside `(0%, 100%)`: zero would floor every
+/// rebalance leg's `min_out` at the full amount (no adapter could ever
+/// satisfy it), and 100% or more would floor it at zero (no protection at
+/// all).
+fn validate_max_slippage(max_slippage_bps: Decimal) -> Result<(), ContractError> {
+    if max_slippage_bps.is_zero() || max_slippage_bps >= Decimal::one() {
+        return Err(ContractError::InvalidSlippage {});
+    }
+    Ok(())
+}
+
+pub fn execute_add_supported_token(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let label = asset.label();
+
+    // Check if token is already supported
+    if config.accepted_denoms.contains(&asset) {
+        return Ok(Response::new()
+            .add_attribute("method", "add_supported_token")
+            .add_attribute("denom", label)
+            .add_attribute("status", "already_supported"));
+    }
+
+    // Add the token to supported list
+    config.accepted_denoms.push(asset);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_supported_token")
+        .add_attribute("denom", label))
+}
+
+pub fn execute_remove_supported_token(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let label = asset.label();
+
+    // Can't remove base denom
+    if asset == AssetInfo::Native(config.base_denom.clone()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Cannot remove base denomination",
+        )));
+    }
+
+    // Check if token is supported
+    if !config.accepted_denoms.contains(&asset) {
+        return Ok(Response::new()
+            .add_attribute("method", "remove_supported_token")
+            .add_attribute("denom", label)
+            .add_attribute("status", "not_supported"));
+    }
+
+    // Remove the token from supported list
+    config.accepted_denoms.retain(|a| a != &asset);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_supported_token")
+        .add_attribute("denom", label))
+}
+
+pub fn execute_update_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    admin: String,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Only proposed here; `admin` itself only changes once the proposed
+    // address calls AcceptAdmin, so a typo can't brick the contract.
+    let validated_admin = deps.api.addr_validate(&admin)?;
+    config.pending_admin = Some(validated_admin);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_admin")
+        .add_attribute("pending_admin", admin))
+}
+
+pub fn execute_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    match config.pending_admin {
+        Some(pending) if pending == info.sender => {
+            config.admin = pending;
+            config.pending_admin = None;
+            CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "accept_admin")
+                .add_attribute("new_admin", config.admin))
+        }
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+pub fn execute_cancel_admin_change(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.pending_admin.is_none() {
+        return Err(ContractError::NoPendingChange {});
+    }
+    config.pending_admin = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("method", "cancel_admin_change"))
+}
+
+pub fn execute_update_ai_operator(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    ai_operator: String,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Only proposed here; `ai_operator` itself only changes once the
+    // proposed address calls AcceptAiOperator.
+    let validated_operator = deps.api.addr_validate(&ai_operator)?;
+    config.pending_ai_operator = Some(validated_operator);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_ai_operator")
+        .add_attribute("pending_ai_operator", ai_operator))
+}
+
+pub fn execute_accept_ai_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    match config.pending_ai_operator {
+        Some(pending) if pending == info.sender => {
+            // `Role::Rebalancer` only follows `config.ai_operator` because
+            // `instantiate` grants it once at setup; `require_role` has no
+            // special case for the config field, so the handover must move
+            // the grant explicitly or the old operator keeps rebalancing
+            // forever and the new one gets `Unauthorized` on every call.
+            revoke_role(deps.storage, &config.ai_operator, Role::Rebalancer)?;
+            grant_role(deps.storage, &pending, Role::Rebalancer)?;
+
+            config.ai_operator = pending;
+            config.pending_ai_operator = None;
+            CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "accept_ai_operator")
+                .add_attribute("new_ai_operator", config.ai_operator))
+        }
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+pub fn execute_cancel_ai_operator_change(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.pending_ai_operator.is_none() {
+        return Err(ContractError::NoPendingChange {});
+    }
+    config.pending_ai_operator = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("method", "cancel_ai_operator_change"))
+}
+
+pub fn execute_set_oracle_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    oracle_addr: String,
+    max_staleness: u64,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    let validated_addr = addr_validate(deps.api, &oracle_addr)?;
+    ORACLE_ADDR.save(deps.storage, &validated_addr)?;
+    ORACLE_MAX_STALENESS.save(deps.storage, &max_staleness)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_oracle_config")
+        .add_attribute("oracle_addr", oracle_addr)
+        .add_attribute("max_staleness", max_staleness.to_string()))
+}
+
+pub fn execute_set_price_feed(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    feed_id: Binary,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    PRICE_FEED_IDS.save(deps.storage, denom.as_str(), &feed_id)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_price_feed")
+        .add_attribute("denom", denom))
+}
+
+pub fn execute_register_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom_a: String,
+    denom_b: String,
+    pair_contract: String,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    let validated_addr = addr_validate(deps.api, &pair_contract)?;
+    let key = crate::token_converter::normalized_pair_key(&denom_a, &denom_b);
+    PAIR_REGISTRY.save(deps.storage, key, &validated_addr)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "register_pair")
+        .add_attribute("denom_a", denom_a)
+        .add_attribute("denom_b", denom_b)
+        .add_attribute("pair_contract", pair_contract))
+}
+
+pub fn execute_register_static_limiter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    protocol: String,
+    upper_bound: Decimal,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    limiters::register_static_limiter(deps.storage, &protocol, upper_bound)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "register_static_limiter")
+        .add_attribute("protocol", protocol)
+        .add_attribute("upper_bound", upper_bound.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_register_change_limiter(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    protocol: String,
+    boundary_offset: Decimal,
+    window_size: u64,
+    division_count: u64,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    limiters::register_change_limiter(
+        deps.storage,
+        &protocol,
+        boundary_offset,
+        window_size,
+        division_count,
+    )?;
+
+    // Seed the moving average with the protocol's current allocation so the
+    // very next rebalance is evaluated against where it stands today rather
+    // than an empty (and therefore unenforced) window.
+    if let Some(existing) = PROTOCOLS.may_load(deps.storage, &protocol)? {
+        limiters::sample_allocation(
+            deps.storage,
+            &protocol,
+            existing.allocation_percentage,
+            env.block.time.seconds(),
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "register_change_limiter")
+        .add_attribute("protocol", protocol)
+        .add_attribute("boundary_offset", boundary_offset.to_string()))
+}
+
+pub fn execute_deregister_limiter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    protocol: String,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::ParamManager)?;
+
+    limiters::deregister_static_limiter(deps.storage, &protocol);
+    limiters::deregister_change_limiter(deps.storage, &protocol);
+
+    Ok(Response::new()
+        .add_attribute("method", "deregister_limiter")
+        .add_attribute("protocol", protocol))
+}
+
+pub fn execute_set_contract_status(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    status: ContractStatus,
+    reason: String,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let status_info = ContractStatusInfo {
+        status,
+        reason: reason.clone(),
+        updated_at: env.block.time,
+    };
+    CONTRACT_STATUS.save(deps.storage, &status_info)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_contract_status")
+        .add_attribute("status", format!("{:?}", status))
+        .add_attribute("reason", reason))
+}
+
+pub fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let validated_addr = addr_validate(deps.api, &address)?;
+    grant_role(deps.storage, &validated_addr, role)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "grant_role")
+        .add_attribute("address", validated_addr)
+        .add_attribute("role", format!("{:?}", role)))
+}
+
+pub fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let validated_addr = addr_validate(deps.api, &address)?;
+    revoke_role(deps.storage, &validated_addr, role)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("address", validated_addr)
+        .add_attribute("role", format!("{:?}", role)))
+}
+
+pub fn execute_set_viewing_key(
+    deps: DepsMut,
+    info: MessageInfo,
+    key: String,
+) -> Result<Response, ContractError> {
+    auth::set_viewing_key(deps.storage, &info.sender, &key)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_viewing_key")
+        .add_attribute("address", info.sender))
+}
+
+pub fn execute_create_viewing_key(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    entropy: String,
+) -> Result<Response, ContractError> {
+    let key = auth::generate_viewing_key(&env, &info, &entropy);
+    auth::set_viewing_key(deps.storage, &info.sender, &key)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "create_viewing_key")
+        .set_data(to_json_binary(&crate::msg::CreateViewingKeyResponse { key })?))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetUserInfo { address } => to_json_binary(&query_user_info(deps, address)?),
+        QueryMsg::GetShares { address } => to_json_binary(&query_shares(deps, address)?),
+        QueryMsg::GetShareValue {} => to_json_binary(&query_share_value(deps)?),
+        QueryMsg::GetUserTxHistory { address, key } => {
+            to_json_binary(&query_user_tx_history(deps, address, key)?)
+        }
+        QueryMsg::WithPermit { permit, query } => {
+            to_json_binary(&query_with_permit(deps, permit, query)?)
+        }
+        QueryMsg::GetProtocols {} => to_json_binary(&query_protocols(deps)?),
+        QueryMsg::GetProtocolInfo { name } => to_json_binary(&query_protocol_info(deps, name)?),
+        QueryMsg::GetRiskParameters {} => to_json_binary(&query_risk_parameters(deps)?),
+        QueryMsg::GetRebalanceHistory { start_after, limit } => {
+            to_json_binary(&query_rebalance_history(deps, start_after, limit)?)
+        }
+        QueryMsg::GetHarvestHistory { start_after, limit } => {
+            to_json_binary(&query_harvest_history(deps, start_after, limit)?)
+        }
+        QueryMsg::GetTotalValue {} => to_json_binary(&query_total_value(deps)?),
+        QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::GetContractStatus {} => to_json_binary(&current_contract_status_info(deps)?),
+        QueryMsg::GetClaims { address } => to_json_binary(&query_claims(deps, address)?),
+        QueryMsg::ReconcileTotalValue {} => {
+            to_json_binary(&query_reconcile_total_value(deps, env)?)
+        }
+        QueryMsg::GetAccruedFees {} => to_json_binary(&query_accrued_fees(deps)?),
+        QueryMsg::GetFeeRecipients {} => to_json_binary(&query_fee_recipients(deps)?),
+        QueryMsg::GetFeeConfig {} => to_json_binary(&query_fee_config(deps)?),
+        QueryMsg::GetProtocolBalances {} => {
+            to_json_binary(&query_protocol_balances(deps, env)?)
+        }
+        QueryMsg::GetRebalancePlan {} => {
+            let (moves, reason) = compute_rebalance_plan(deps)?;
+            to_json_binary(&GetRebalancePlanResponse { moves, reason })
+        }
+        QueryMsg::GetDepositQuote { denom, amount } => {
+            to_json_binary(&query_deposit_quote(deps, env, denom, amount)?)
+        }
+        QueryMsg::GetPriceFeeds {} => to_json_binary(&query_price_feeds(deps)?),
+        QueryMsg::GetPairContract { denom_a, denom_b } => {
+            to_json_binary(&query_pair_contract(deps, denom_a, denom_b)?)
+        }
+        QueryMsg::GetRoles { address } => to_json_binary(&query_roles(deps, address)?),
+        QueryMsg::SimulateRebalance { target_allocations } => {
+            to_json_binary(&query_simulate_rebalance(deps, env, target_allocations)?)
+        }
+        QueryMsg::GetUserTransactions {
+            address,
+            start_after,
+            limit,
+        } => to_json_binary(&query_user_transactions(deps, address, start_after, limit)?),
+        QueryMsg::GetTwapPrice { denom, window_secs } => {
+            to_json_binary(&query_twap_price(deps, env, denom, window_secs)?)
+        }
+    }
+}
+
+/// Reads back whatever TWAP history has accumulated for `denom` without
+/// refreshing it - refreshing only happens as a side effect of
+/// `refresh_and_check_twap_deviation` on `Rebalance`, since a query can't
+/// write state.
+fn query_twap_price(
+    deps: Deps,
+    env: Env,
+    denom: String,
+    window_secs: u64,
+) -> StdResult<GetTwapPriceResponse> {
+    let twap_price = twap::twap_since_genesis(deps.storage, &denom, window_secs, env.block.time)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    Ok(match twap_price {
+        Some(twap_price) => GetTwapPriceResponse {
+            twap_price,
+            has_sufficient_data: true,
+        },
+        None => GetTwapPriceResponse {
+            twap_price: Decimal::zero(),
+            has_sufficient_data: false,
+        },
+    })
+}
+
+fn load_user_info(deps: Deps, addr: &Addr) -> StdResult<UserInfo> {
+    Ok(USER_INFOS.may_load(deps.storage, addr)?.unwrap_or(UserInfo {
+        deposits: vec![],
+        cost_basis: Uint128::zero(),
+    }))
+}
+
+/// Looks up `addr`'s vault shares and their current redemption value
+/// (`shares * total_assets / total_shares`). Shared by every `GetUserInfo`
+/// read path since the value moves with every other depositor's activity,
+/// not just this address's own deposits/withdrawals.
+fn load_user_shares(deps: Deps, addr: &Addr) -> StdResult<(Uint128, Uint128)> {
+    let shares = SHARES.may_load(deps.storage, addr)?.unwrap_or_default();
+    let total_shares = TOTAL_SHARES.load(deps.storage)?;
+    let total_assets = TOTAL_USDC_VALUE.load(deps.storage)?;
+    Ok((shares, assets_for_shares(shares, total_shares, total_assets)))
+}
+
+/// Public aggregate-only read: anyone can check an address's `shares`,
+/// `asset_value`, and `cost_basis`, but the returned `deposits` is always
+/// empty. The per-deposit breakdown is sensitive (amounts, timing, tokens)
+/// and only comes back through the authenticated `GetUserTxHistory` or
+/// `WithPermit` paths.
+fn query_user_info(deps: Deps, address: String) -> StdResult<GetUserInfoResponse> {
+    // In tests, skip validation
+    #[cfg(test)]
+    let addr = Addr::unchecked(&address);
+
+    // In production, validate the address
+    #[cfg(not(test))]
+    let addr = deps.api.addr_validate(&address)?;
 
-    let user_info = USER_INFOS
-        .may_load(deps.storage, &addr)?
-        .unwrap_or(UserInfo {
-            total_usdc_value: Uint128::zero(),
+    let user_info = load_user_info(deps, &addr)?;
+    let (shares, asset_value) = load_user_shares(deps, &addr)?;
+
+    Ok(GetUserInfoResponse {
+        user_info: UserInfo {
             deposits: vec![],
-        });
+            ..user_info
+        },
+        shares,
+        asset_value,
+    })
+}
+
+/// Just the vault shares `address` holds, for callers that don't need the
+/// rest of `GetUserInfo`'s aggregate.
+fn query_shares(deps: Deps, address: String) -> StdResult<GetSharesResponse> {
+    #[cfg(test)]
+    let addr = Addr::unchecked(&address);
+
+    #[cfg(not(test))]
+    let addr = deps.api.addr_validate(&address)?;
+
+    let shares = SHARES.may_load(deps.storage, &addr)?.unwrap_or_default();
+
+    Ok(GetSharesResponse { shares })
+}
+
+/// The vault-wide share price inputs every address's `asset_value` is
+/// derived from.
+fn query_share_value(deps: Deps) -> StdResult<GetShareValueResponse> {
+    Ok(GetShareValueResponse {
+        total_shares: TOTAL_SHARES.load(deps.storage)?,
+        total_assets: TOTAL_USDC_VALUE.load(deps.storage)?,
+    })
+}
+
+fn query_user_tx_history(
+    deps: Deps,
+    address: String,
+    key: Option<String>,
+) -> StdResult<GetUserTxHistoryResponse> {
+    #[cfg(test)]
+    let addr = Addr::unchecked(&address);
+
+    #[cfg(not(test))]
+    let addr = deps.api.addr_validate(&address)?;
+
+    auth::verify_viewing_key(deps.storage, &addr, key.as_deref())?;
+
+    Ok(GetUserTxHistoryResponse {
+        deposits: load_user_info(deps, &addr)?.deposits,
+    })
+}
 
-    Ok(GetUserInfoResponse { user_info })
+fn query_with_permit(
+    deps: Deps,
+    permit: crate::auth::QueryPermit,
+    query: PermitQuery,
+) -> StdResult<PermitQueryResponse> {
+    let signer = permit.params.signer.clone();
+    permit.verify(deps.api, &signer, query.clone())?;
+
+    match query {
+        PermitQuery::UserInfo => {
+            let (shares, asset_value) = load_user_shares(deps, &signer)?;
+            Ok(PermitQueryResponse::UserInfo(GetUserInfoResponse {
+                user_info: load_user_info(deps, &signer)?,
+                shares,
+                asset_value,
+            }))
+        }
+        PermitQuery::TxHistory => Ok(PermitQueryResponse::TxHistory(GetUserTxHistoryResponse {
+            deposits: load_user_info(deps, &signer)?.deposits,
+        })),
+    }
 }
 
 fn query_protocols(deps: Deps) -> StdResult<GetProtocolsResponse> {
@@ -1015,18 +3126,47 @@ fn query_risk_parameters(deps: Deps) -> StdResult<GetRiskParametersResponse> {
 
 fn query_rebalance_history(
     deps: Deps,
+    start_after: Option<u64>,
     limit: Option<u32>,
 ) -> StdResult<GetRebalanceHistoryResponse> {
-    let history = REBALANCE_HISTORY.load(deps.storage)?;
-    let limit_val = limit.unwrap_or(history.len() as u32) as usize;
+    let history = tx_log::query_tx_log(
+        deps.storage,
+        None,
+        Some(&TxKind::Rebalance),
+        start_after,
+        limit,
+    )?;
 
-    // Reverse the history to return newest first
-    let limited_history: Vec<RebalanceRecord> =
-        history.iter().rev().take(limit_val).cloned().collect();
+    Ok(GetRebalanceHistoryResponse { history })
+}
 
-    Ok(GetRebalanceHistoryResponse {
-        history: limited_history,
-    })
+fn query_harvest_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GetHarvestHistoryResponse> {
+    let history = tx_log::query_tx_log(
+        deps.storage,
+        None,
+        Some(&TxKind::Harvest),
+        start_after,
+        limit,
+    )?;
+
+    Ok(GetHarvestHistoryResponse { history })
+}
+
+/// The ledger entries `address` was the actor of, newest first.
+fn query_user_transactions(
+    deps: Deps,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GetUserTransactionsResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let transactions = tx_log::query_tx_log(deps.storage, Some(&addr), None, start_after, limit)?;
+
+    Ok(GetUserTransactionsResponse { transactions })
 }
 
 fn query_total_value(deps: Deps) -> StdResult<GetTotalValueResponse> {
@@ -1034,6 +3174,186 @@ fn query_total_value(deps: Deps) -> StdResult<GetTotalValueResponse> {
     Ok(GetTotalValueResponse { total_value })
 }
 
+/// Queries every enabled protocol's live balance via its `YieldProtocol` adapter.
+/// Shared by `SyncBalances` (persists the result) and `GetProtocolBalances`
+/// (returns it without persisting).
+fn live_protocol_balances(deps: Deps, env: &Env) -> StdResult<Vec<(String, Uint128)>> {
+    let protocol_names: Vec<String> = PROTOCOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
+
+    let mut balances = vec![];
+    for name in protocol_names {
+        let protocol = PROTOCOLS.load(deps.storage, &name)?;
+        if !protocol.enabled {
+            continue;
+        }
+
+        let adapter = create_protocol_adapter(
+            &name,
+            protocol.contract_addr.clone(),
+            name.clone(),
+            protocol.deposit_asset.clone(),
+        )?;
+        let live_balance = adapter.query_balance(deps, env.clone())?;
+        balances.push((name, live_balance));
+    }
+
+    Ok(balances)
+}
+
+/// Crank that writes each enabled protocol's live on-chain balance into
+/// `ProtocolInfo.current_balance` and recomputes `TOTAL_USDC_VALUE`, so
+/// allocation bookkeeping reflects real state between rebalances rather than
+/// only the local accounting `AddProtocol`/deposits/withdrawals leave behind.
+/// Restricted to `Role::Rebalancer` (or the admin) since it rewrites
+/// vault-wide accounting that every share price derives from.
+pub fn execute_sync_balances(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    require_role(deps.as_ref(), &info.sender, Role::Rebalancer)?;
+
+    let (total, drift_attributes) = sync_protocol_balances(deps, &env)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "sync_balances")
+        .add_attribute("protocols_synced", drift_attributes.len().to_string())
+        .add_attribute("total_usdc_value", total.to_string())
+        .add_attributes(drift_attributes))
+}
+
+/// Queries every enabled protocol's live balance, writes it into
+/// `ProtocolInfo.current_balance`, and recomputes `TOTAL_USDC_VALUE` from
+/// the fresh numbers plus whatever else still backs shares: disabled
+/// protocols' last-known `current_balance` (left in place until someone
+/// withdraws it, not zero) and the vault's own idle cash net of amounts
+/// already earmarked elsewhere (accrued performance fees, queued withdrawal
+/// claims). Returns the new total alongside a `drift_<protocol>` attribute
+/// per enabled protocol (signed, stored-balance-relative) so callers —
+/// including `execute_withdraw`/`execute_emergency_withdraw` syncing before
+/// a payout — can see exactly what moved, not just the new aggregate.
+fn sync_protocol_balances(
+    deps: DepsMut,
+    env: &Env,
+) -> Result<(Uint128, Vec<(String, String)>), ContractError> {
+    let balances = live_protocol_balances(deps.as_ref(), env)?;
+
+    let mut total = Uint128::zero();
+    let mut drift_attributes = vec![];
+    for (name, balance) in &balances {
+        let stored_balance = PROTOCOLS.load(deps.storage, name)?.current_balance;
+
+        PROTOCOLS.update(deps.storage, name, |protocol| -> StdResult<_> {
+            let mut protocol = protocol.ok_or_else(|| StdError::not_found("ProtocolInfo"))?;
+            protocol.current_balance = *balance;
+            Ok(protocol)
+        })?;
+
+        let drift: i128 = balance.u128() as i128 - stored_balance.u128() as i128;
+        drift_attributes.push((format!("drift_{name}"), drift.to_string()));
+        total += *balance;
+    }
+
+    let protocol_names: Vec<String> = PROTOCOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
+    for name in protocol_names {
+        let protocol = PROTOCOLS.load(deps.storage, &name)?;
+        if !protocol.enabled {
+            total += protocol.current_balance;
+        }
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let idle_cash = deps
+        .querier
+        .query_balance(&env.contract.address, config.base_denom.clone())?
+        .amount;
+    let accrued_fees = ACCRUED_FEES.load(deps.storage)?;
+    let pending_claims = pending_claims_total(deps.storage, &config.base_denom)?;
+    total += idle_cash.saturating_sub(accrued_fees + pending_claims);
+
+    TOTAL_USDC_VALUE.save(deps.storage, &total)?;
+
+    Ok((total, drift_attributes))
+}
+
+/// Sums every queued `Claim`, across every address, denominated in `denom`.
+/// `sync_protocol_balances` nets this against the vault's idle cash so a
+/// withdrawal already queued for payout isn't counted twice: once as the
+/// user's claim and once as unbacked idle cash still sitting in the vault.
+fn pending_claims_total(
+    storage: &dyn cosmwasm_std::Storage,
+    denom: &str,
+) -> StdResult<Uint128> {
+    let mut total = Uint128::zero();
+    for item in CLAIMS.range(storage, None, None, Order::Ascending) {
+        let (_, claims) = item?;
+        for claim in claims {
+            if claim.denom == denom {
+                total += claim.amount;
+            }
+        }
+    }
+    Ok(total)
+}
+
+/// Queries each enabled protocol's real on-chain balance via `YieldProtocol::query_balance`
+/// and compares the sum against the stored `total_usdc_value`, so an operator can catch a
+/// diverged protocol integration (accrued yield, a loss, a stuck withdrawal) before rebalancing
+/// on stale numbers.
+fn query_reconcile_total_value(deps: Deps, env: Env) -> StdResult<ReconcileTotalValueResponse> {
+    let stored_total = TOTAL_USDC_VALUE.load(deps.storage)?;
+
+    let protocol_names: Vec<String> = PROTOCOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| key.unwrap())
+        .collect();
+
+    let mut live_total = Uint128::zero();
+    let mut protocols = vec![];
+
+    for name in protocol_names {
+        let protocol = PROTOCOLS.load(deps.storage, &name)?;
+        if !protocol.enabled {
+            continue;
+        }
+
+        let adapter = create_protocol_adapter(
+            &name,
+            protocol.contract_addr.clone(),
+            name.clone(),
+            protocol.deposit_asset.clone(),
+        )?;
+        let live_balance = adapter.query_balance(deps, env.clone())?;
+
+        live_total += live_balance;
+        protocols.push(ProtocolBalanceSnapshot {
+            name,
+            stored_balance: protocol.current_balance,
+            live_balance,
+        });
+    }
+
+    let drift = if live_total >= stored_total {
+        live_total - stored_total
+    } else {
+        stored_total - live_total
+    };
+
+    Ok(ReconcileTotalValueResponse {
+        stored_total,
+        live_total,
+        drift,
+        live_exceeds_stored: live_total > stored_total,
+        protocols,
+    })
+}
+
 fn query_config(deps: Deps) -> StdResult<crate::msg::Config> {
     let state_config = CONFIG.load(deps.storage)?;
 
@@ -1044,9 +3364,147 @@ fn query_config(deps: Deps) -> StdResult<crate::msg::Config> {
         base_denom: state_config.base_denom,
         accepted_denoms: state_config.accepted_denoms,
         astroport_router: state_config.astroport_router,
+        unbonding_period: state_config.unbonding_period,
+        performance_fee_bps: state_config.performance_fee_bps,
+        fee_collector: state_config.fee_collector,
+        pending_admin: state_config.pending_admin,
+        pending_ai_operator: state_config.pending_ai_operator,
+    })
+}
+
+fn query_accrued_fees(deps: Deps) -> StdResult<GetAccruedFeesResponse> {
+    Ok(GetAccruedFeesResponse {
+        accrued_fees: ACCRUED_FEES.load(deps.storage)?,
+    })
+}
+
+fn query_fee_recipients(deps: Deps) -> StdResult<GetFeeRecipientsResponse> {
+    Ok(GetFeeRecipientsResponse {
+        recipients: FEE_RECIPIENTS.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+fn query_fee_config(deps: Deps) -> StdResult<GetFeeConfigResponse> {
+    Ok(GetFeeConfigResponse {
+        performance_fee: RISK_PARAMETERS.load(deps.storage)?.performance_fee,
+        recipients: FEE_RECIPIENTS.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+/// Freshly queries each enabled protocol's live balance without persisting it,
+/// pairing it with the stored `current_balance` so a caller can see drift
+/// side by side and preview what `SyncBalances` would write.
+fn query_protocol_balances(deps: Deps, env: Env) -> StdResult<GetProtocolBalancesResponse> {
+    let balances = live_protocol_balances(deps, &env)?;
+    let total = balances
+        .iter()
+        .fold(Uint128::zero(), |acc, (_, balance)| acc + balance);
+
+    let protocols = balances
+        .into_iter()
+        .map(|(name, live_balance)| -> StdResult<_> {
+            let stored_balance = PROTOCOLS.load(deps.storage, &name)?.current_balance;
+            Ok(ProtocolBalance {
+                name,
+                stored_balance,
+                live_balance,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GetProtocolBalancesResponse { protocols, total })
+}
+
+/// Previews what `execute_deposit` would credit for `amount` of `denom`
+/// without actually depositing, using the same oracle-backed valuation so
+/// front-ends can quote a price before sending funds.
+fn query_deposit_quote(
+    deps: Deps,
+    env: Env,
+    denom: String,
+    amount: Uint128,
+) -> StdResult<GetDepositQuoteResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if denom == config.base_denom {
+        return Ok(GetDepositQuoteResponse { usdc_value: amount });
+    }
+
+    let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+    let oracle_addr = ORACLE_ADDR.load(deps.storage)?;
+    let feed_id = PRICE_FEED_IDS
+        .may_load(deps.storage, denom.as_str())?
+        .ok_or_else(|| StdError::generic_err(format!("No price feed registered for {denom}")))?;
+
+    let usdc_value = oracle::query_conservative_deposit_value(
+        deps,
+        &oracle_addr,
+        &feed_id,
+        amount,
+        env.block.time,
+        risk_parameters.max_price_staleness,
+    )?;
+
+    Ok(GetDepositQuoteResponse { usdc_value })
+}
+
+/// Lists every denom with a registered Pyth price feed, alongside the
+/// oracle contract and staleness bound deposits/withdrawals check it
+/// against, so operators can audit what's configured without guessing at
+/// storage keys.
+fn query_price_feeds(deps: Deps) -> StdResult<GetPriceFeedsResponse> {
+    let oracle_addr = ORACLE_ADDR.may_load(deps.storage)?;
+    let max_staleness = ORACLE_MAX_STALENESS.may_load(deps.storage)?.unwrap_or_default();
+
+    let feeds = PRICE_FEED_IDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, feed_id) = item?;
+            Ok(PriceFeedEntry { denom, feed_id })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GetPriceFeedsResponse {
+        oracle_addr,
+        max_staleness,
+        feeds,
+    })
+}
+
+fn query_pair_contract(
+    deps: Deps,
+    denom_a: String,
+    denom_b: String,
+) -> StdResult<GetPairContractResponse> {
+    let key = crate::token_converter::normalized_pair_key(&denom_a, &denom_b);
+    Ok(GetPairContractResponse {
+        pair_contract: PAIR_REGISTRY.may_load(deps.storage, key)?,
     })
 }
 
+fn query_roles(deps: Deps, address: String) -> StdResult<GetRolesResponse> {
+    #[cfg(test)]
+    let addr = Addr::unchecked(&address);
+
+    #[cfg(not(test))]
+    let addr = deps.api.addr_validate(&address)?;
+
+    let roles = ROLES.may_load(deps.storage, &addr)?.unwrap_or_default();
+
+    Ok(GetRolesResponse { roles })
+}
+
+fn query_claims(deps: Deps, address: String) -> StdResult<GetClaimsResponse> {
+    #[cfg(test)]
+    let addr = Addr::unchecked(&address);
+
+    #[cfg(not(test))]
+    let addr = deps.api.addr_validate(&address)?;
+
+    let claims = CLAIMS.may_load(deps.storage, &addr)?.unwrap_or_default();
+    Ok(GetClaimsResponse { claims })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1072,6 +3530,9 @@ mod tests {
     fn router_address() -> String {
         addr("router")
     }
+    fn fee_collector_address() -> String {
+        addr("fee_collector")
+    }
 
     // Helper function to setup contract with valid addresses
     fn setup_contract(deps: DepsMut) {
@@ -1079,14 +3540,24 @@ mod tests {
             admin: admin_address(),
             ai_operator: operator_address(),
             base_denom: DENOM.to_string(),
-            accepted_denoms: vec![DENOM.to_string(), "inj".to_string()],
+            accepted_denoms: vec![
+                AssetInfo::Native(DENOM.to_string()),
+                AssetInfo::Native("inj".to_string()),
+            ],
             astroport_router: router_address(),
             risk_parameters: RiskParametersMsg {
                 max_allocation_per_protocol: Decimal::percent(50),
                 max_slippage: Decimal::percent(1),
                 rebalance_threshold: Decimal::percent(5),
                 emergency_withdrawal_fee: Decimal::percent(1),
+                max_price_staleness: 60,
+                performance_fee: Decimal::percent(10),
+                max_price_deviation: Decimal::percent(5),
+                max_slippage_bps: Decimal::percent(1),
             },
+            unbonding_period: None,
+            performance_fee_bps: 1000,
+            fee_collector: fee_collector_address(),
         };
 
         let info = message_info(&Addr::unchecked(creator_address()), &[]);
@@ -1,3 +1,4 @@
+use crate::state::ContractStatus;
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
@@ -53,6 +54,54 @@ pub enum ContractError {
 
     #[error("Emergency mode active")]
     EmergencyModeActive {},
+
+    #[error("Oracle price is missing or non-positive")]
+    InvalidPrice {},
+
+    #[error("Oracle price published at {publish_time} is older than allowed as of {now}")]
+    StalePrice { publish_time: i64, now: i64 },
+
+    #[error("Oracle valuation diverges from the router quote by more than max_slippage")]
+    OracleDivergence {},
+
+    #[error("Allocation change exceeds the configured limiter bound")]
+    AllocationChangeTooLarge {},
+
+    #[error("Operation paused by contract status: {status:?}")]
+    OperationPaused { status: ContractStatus },
+
+    #[error("No claims have matured yet")]
+    NoMaturedClaims {},
+
+    #[error("Deposit converts to zero USDC value")]
+    ZeroValueDeposit {},
+
+    #[error("No pending admin/ai_operator change to accept or cancel")]
+    NoPendingChange {},
+
+    #[error("Pool reserves are too thin to honor this swap within max_slippage")]
+    SlippageExceeded {},
+
+    #[error("Asset mismatch: expected {expected}, received {received}")]
+    AssetMismatch { expected: String, received: String },
+
+    #[error("No accrued fees to claim")]
+    NoFeesToClaim {},
+
+    #[error("Live spot price diverges from the TWAP by more than max_price_deviation")]
+    PriceDeviationTooHigh {},
+
+    #[error("max_slippage_bps must be strictly between 0% and 100%")]
+    InvalidSlippage {},
+
+    #[error("A rebalance is already in progress")]
+    RebalanceAlreadyInProgress {},
+
+    #[error("A reward harvest is already in progress")]
+    HarvestAlreadyInProgress {},
+
+    #[error("Contract state is already at version {version}, refusing to migrate again")]
+    AlreadyMigrated { version: u64 },
 }
 
 impl From<ContractError> for StdError {
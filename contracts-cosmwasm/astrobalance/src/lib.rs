@@ -1,11 +1,18 @@
+pub mod auth;
 pub mod contract;
 mod error;
 pub mod helpers;
+pub mod limiters;
 pub mod msg;
+pub mod oracle;
+pub mod permissions;
 pub mod protocols;
 pub mod state;
 pub mod strategy_executor;
+pub mod swap_math;
 pub mod token_converter;
+pub mod twap;
+pub mod tx_log;
 
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,187 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Order, StdResult, Storage};
+use cw_storage_plus::Map;
+
+use crate::error::ContractError;
+
+/// Caps a protocol's allocation at an absolute upper bound, independent of
+/// how quickly it got there.
+#[cw_serde]
+pub struct StaticLimiterConfig {
+    pub upper_bound: Decimal,
+}
+
+/// Rejects a rebalance that moves a protocol's allocation further than
+/// `boundary_offset` away from its recent moving average.
+#[cw_serde]
+pub struct ChangeLimiterConfig {
+    pub boundary_offset: Decimal,
+    /// Seconds covered by the moving average.
+    pub window_size: u64,
+    /// Number of buckets the window is divided into; a new sample is only
+    /// recorded once per `window_size / division_count` seconds.
+    pub division_count: u64,
+}
+
+#[cw_serde]
+pub struct LimiterSample {
+    pub timestamp: u64,
+    pub value: Decimal,
+}
+
+#[cw_serde]
+pub struct ChangeLimiterState {
+    pub config: ChangeLimiterConfig,
+    pub samples: Vec<LimiterSample>,
+}
+
+pub const STATIC_LIMITERS: Map<&str, StaticLimiterConfig> = Map::new("static_limiters");
+pub const CHANGE_LIMITERS: Map<&str, ChangeLimiterState> = Map::new("change_limiters");
+
+pub fn register_static_limiter(
+    storage: &mut dyn Storage,
+    protocol: &str,
+    upper_bound: Decimal,
+) -> StdResult<()> {
+    STATIC_LIMITERS.save(storage, protocol, &StaticLimiterConfig { upper_bound })
+}
+
+pub fn deregister_static_limiter(storage: &mut dyn Storage, protocol: &str) {
+    STATIC_LIMITERS.remove(storage, protocol);
+}
+
+pub fn register_change_limiter(
+    storage: &mut dyn Storage,
+    protocol: &str,
+    boundary_offset: Decimal,
+    window_size: u64,
+    division_count: u64,
+) -> StdResult<()> {
+    CHANGE_LIMITERS.save(
+        storage,
+        protocol,
+        &ChangeLimiterState {
+            config: ChangeLimiterConfig {
+                boundary_offset,
+                window_size,
+                division_count,
+            },
+            samples: vec![],
+        },
+    )
+}
+
+pub fn deregister_change_limiter(storage: &mut dyn Storage, protocol: &str) {
+    CHANGE_LIMITERS.remove(storage, protocol);
+}
+
+/// Drop samples older than `window_size` and return the average of what's
+/// left, treating an empty window as "no reference yet" (always passes).
+fn moving_average(state: &ChangeLimiterState, now: u64) -> Option<Decimal> {
+    let cutoff = now.saturating_sub(state.config.window_size);
+    let fresh: Vec<&LimiterSample> = state.samples.iter().filter(|s| s.timestamp >= cutoff).collect();
+
+    if fresh.is_empty() {
+        return None;
+    }
+
+    let sum: Decimal = fresh.iter().map(|s| s.value).sum();
+    Some(sum / Decimal::from_ratio(fresh.len() as u128, 1u128))
+}
+
+/// Validate a proposed allocation against both limiter kinds registered for
+/// `protocol`. Either limiter being unregistered means that check is skipped.
+pub fn check_limiters(
+    storage: &dyn Storage,
+    protocol: &str,
+    proposed_allocation: Decimal,
+) -> Result<(), ContractError> {
+    if let Some(static_limiter) = STATIC_LIMITERS.may_load(storage, protocol)? {
+        if proposed_allocation > static_limiter.upper_bound {
+            return Err(ContractError::AllocationChangeTooLarge {});
+        }
+    }
+
+    if let Some(change_limiter) = CHANGE_LIMITERS.may_load(storage, protocol)? {
+        // Averages every sample currently stored for this protocol. Samples
+        // older than `window_size` are only dropped when `sample_allocation`
+        // runs (at limiter registration and after each committed
+        // rebalance); this check reads without a "now" and drops none.
+        if let Some(average) = latest_average(&change_limiter) {
+            if proposed_allocation > average + change_limiter.config.boundary_offset {
+                return Err(ContractError::AllocationChangeTooLarge {});
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn latest_average(state: &ChangeLimiterState) -> Option<Decimal> {
+    if state.samples.is_empty() {
+        return None;
+    }
+    let sum: Decimal = state.samples.iter().map(|s| s.value).sum();
+    Some(sum / Decimal::from_ratio(state.samples.len() as u128, 1u128))
+}
+
+/// Roll the bucketed moving average forward: drop samples older than
+/// `window_size`, and record a new sample only if a full bucket period
+/// (`window_size / division_count`) has elapsed since the last one.
+pub fn sample_allocation(
+    storage: &mut dyn Storage,
+    protocol: &str,
+    allocation: Decimal,
+    now: u64,
+) -> StdResult<()> {
+    let Some(mut state) = CHANGE_LIMITERS.may_load(storage, protocol)? else {
+        return Ok(());
+    };
+
+    let cutoff = now.saturating_sub(state.config.window_size);
+    state.samples.retain(|s| s.timestamp >= cutoff);
+
+    let bucket_period = if state.config.division_count == 0 {
+        state.config.window_size
+    } else {
+        state.config.window_size / state.config.division_count.max(1)
+    };
+
+    let should_sample = match state.samples.last() {
+        Some(last) => now.saturating_sub(last.timestamp) >= bucket_period,
+        None => true,
+    };
+
+    if should_sample {
+        state.samples.push(LimiterSample {
+            timestamp: now,
+            value: allocation,
+        });
+    } else if let Some(last) = state.samples.last_mut() {
+        // Same bucket: keep the reference point current without inflating
+        // the sample count.
+        last.value = allocation;
+    }
+
+    let _ = moving_average(&state, now);
+    CHANGE_LIMITERS.save(storage, protocol, &state)
+}
+
+/// Clear all recorded samples for every registered change limiter so that an
+/// emergency-path allocation shift isn't later blocked by a stale average.
+/// Registered limiter *configs* are left intact.
+pub fn reset_limiter_states(storage: &mut dyn Storage) -> StdResult<()> {
+    let protocols: Vec<String> = CHANGE_LIMITERS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for protocol in protocols {
+        CHANGE_LIMITERS.update(storage, &protocol, |state| -> StdResult<_> {
+            let mut state = state.unwrap();
+            state.samples.clear();
+            Ok(state)
+        })?;
+    }
+
+    Ok(())
+}
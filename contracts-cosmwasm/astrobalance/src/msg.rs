@@ -1,40 +1,104 @@
-use crate::state::{ProtocolInfo, RebalanceRecord, RiskParameters, UserInfo};
+use crate::auth::{PermitQuery, QueryPermit};
+use crate::permissions::Role;
+use crate::protocols::cw20::Cw20ReceiveMsg;
+use crate::state::{
+    AssetInfo, Claim, ContractStatus, ContractStatusInfo, ProtocolInfo, RiskParameters,
+    UserDeposit, UserInfo,
+};
+use crate::tx_log::TxRecord;
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admin: String,
     pub ai_operator: String,
-    pub base_denom: String,           // USDC
-    pub accepted_denoms: Vec<String>, // Initial supported tokens
+    pub base_denom: String, // USDC
+    // Initial supported deposit/withdraw assets, native or cw20.
+    pub accepted_denoms: Vec<AssetInfo>,
     pub astroport_router: String,     // Astroport router address
     pub risk_parameters: RiskParametersMsg,
+    // Seconds a `Withdraw` must wait in the claims queue; `None` pays out
+    // immediately as before.
+    pub unbonding_period: Option<u64>,
+    // Cut of realized gains taken on `Withdraw`/`Claim`, in basis points.
+    pub performance_fee_bps: u16,
+    // Recipient of the performance fee.
+    pub fee_collector: String,
 }
 
+// Empty for now; `migrate` derives everything it needs from the stored
+// `CONTRACT_STATE_VERSION` rather than anything the caller passes in.
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub struct RiskParametersMsg {
     pub max_allocation_per_protocol: Decimal,
     pub max_slippage: Decimal,
     pub rebalance_threshold: Decimal,
     pub emergency_withdrawal_fee: Decimal,
+    // Seconds a Pyth price (spot or EMA) may sit unpublished before a
+    // deposit valuation rejects it as stale.
+    pub max_price_staleness: u64,
+    // Cut of vault-wide realized yield taken on each `Rebalance`, split
+    // across `FEE_RECIPIENTS`. Distinct from `Config.performance_fee_bps`,
+    // which is charged per-user on `Withdraw`/`Claim` instead.
+    pub performance_fee: Decimal,
+    // Max allowed relative divergence between a live Astroport spot quote
+    // and the TWAP oracle before `Rebalance` rejects with
+    // `PriceDeviationTooHigh`.
+    pub max_price_deviation: Decimal,
+    // Floor applied to every rebalance leg's minimum-received amount, as
+    // `amount * (1 - max_slippage_bps)`. Must be strictly between 0% and
+    // 100%; `InstantiateMsg`/`UpdateRiskParameters` reject anything else.
+    pub max_slippage_bps: Decimal,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     // User operations
-    Deposit {}, // Token info obtained from sent funds
+    Deposit {
+        // Token info obtained from sent funds. Credits `info.sender` unless
+        // a recipient is given, validated and used in place of the sender
+        // throughout accounting so this can safely deposit on another
+        // account's behalf.
+        recipient: Option<String>,
+    },
     Withdraw {
         amount: Uint128,
         denom: Option<String>, // If None, withdraw in base_denom (USDC)
+        // If true, refreshes every protocol's live on-chain balance (as
+        // `SyncBalances` would) before pricing shares and splitting the
+        // payout, so the withdrawal is based on real positions rather than
+        // whatever bookkeeping the last deposit/rebalance left behind.
+        // Defaults to false to match the previous behavior.
+        sync_balances: Option<bool>,
+        // If true and `denom` names a non-base denom with a registered
+        // pair (see `RegisterPair`), `amount` is read as the exact quantity
+        // of `denom` to receive rather than a USDC redemption value: it's
+        // translated to the required USDC up front via the pair's
+        // `ReverseSimulation`, so e.g. requesting exactly 100 INJ debits
+        // only the USDC that costs. Ignored for base_denom withdrawals,
+        // where `amount` is already exact. Defaults to false.
+        exact_output: Option<bool>,
     },
-    EmergencyWithdraw {},
+    EmergencyWithdraw {
+        sync_balances: Option<bool>,
+    },
+    Claim {},
+    // Cw20 hook entry point: fires when an accepted cw20 token's `Send`
+    // lands tokens on this contract. `msg` decodes to `Cw20HookMsg`. The
+    // cw20 contract address (`info.sender` at the handler) must already be
+    // whitelisted via `AddSupportedToken`, the same way a native denom is.
+    Receive(Cw20ReceiveMsg),
 
     // Protocol management
     AddProtocol {
         name: String,
         contract_addr: String,
         initial_allocation: Decimal,
+        deposit_asset: AssetInfo,
     },
     RemoveProtocol {
         name: String,
@@ -49,32 +113,189 @@ pub enum ExecuteMsg {
     Rebalance {
         target_allocations: Vec<(String, Decimal)>,
         reason: String,
+        // If true, refreshes every protocol's live on-chain balance (as
+        // `SyncBalances` would) before sizing moves, and derives each
+        // protocol's current percentage from that live balance instead of
+        // the stored `allocation_percentage`, so drift from accrued yield
+        // or exchange-rate changes since the last sync doesn't throw off
+        // the amounts moved. Defaults to false to match the previous
+        // behavior.
+        sync_balances: Option<bool>,
     },
     UpdateRiskParameters {
         risk_parameters: RiskParametersMsg,
     },
 
     // Admin functions
+    // Sets the weighted split of the vault-wide performance fee. Weights
+    // must sum to `Decimal::one()` or this fails with `InvalidAllocations`.
+    SetFeeRecipients {
+        recipients: Vec<(String, Decimal)>,
+    },
+    // Combines `UpdateRiskParameters`'s `performance_fee` and
+    // `SetFeeRecipients` into one call, for a single fee-settings update
+    // instead of two transactions that could otherwise land with a
+    // momentarily inconsistent rate/split pair in between. Same
+    // `InvalidAllocations` validation as `SetFeeRecipients`.
+    UpdateFeeConfig {
+        performance_fee: Decimal,
+        recipients: Vec<(String, Decimal)>,
+    },
+    // Pays out the current `ACCRUED_FEES` balance across `FEE_RECIPIENTS`
+    // (falling back to `fee_collector` if no recipients are registered),
+    // then resets the accrued total to zero. Fails with `NoFeesToClaim` if
+    // nothing has accrued.
+    ClaimFees {},
+    // `asset` may be `AssetInfo::Cw20`: `Receive` checks the calling token
+    // contract against this same whitelist, so a cw20 asset is supported by
+    // adding its contract address here.
     AddSupportedToken {
-        denom: String,
+        asset: AssetInfo,
     },
     RemoveSupportedToken {
-        denom: String,
+        asset: AssetInfo,
     },
+    // Proposes `admin` as the next admin; takes effect only once that
+    // address calls `AcceptAdmin`, so a typo'd address can't permanently
+    // lock out control of the contract.
     UpdateAdmin {
         admin: String,
     },
+    // Called by the pending admin to finalize a handover proposed by
+    // `UpdateAdmin`.
+    AcceptAdmin {},
+    // Called by the current admin to withdraw an `UpdateAdmin` proposal
+    // before it's accepted.
+    CancelAdminChange {},
+
+    // Same propose/accept/cancel handover as admin, for `ai_operator`.
     UpdateAiOperator {
         ai_operator: String,
     },
+    AcceptAiOperator {},
+    CancelAiOperatorChange {},
+
+    // Oracle configuration
+    SetOracleConfig {
+        oracle_addr: String,
+        max_staleness: u64,
+    },
+    SetPriceFeed {
+        denom: String,
+        feed_id: Binary,
+    },
+
+    // Registers the Astroport pair contract that directly swaps
+    // `denom_a`/`denom_b`, letting `AstroportRouter::convert_to_usdc`/
+    // `convert_from_usdc` swap against it instead of hopping through the
+    // router's `SimulateSwapOperations`/`ExecuteSwapOperations`.
+    RegisterPair {
+        denom_a: String,
+        denom_b: String,
+        pair_contract: String,
+    },
+
+    // Rebalance rate-limiting
+    RegisterStaticLimiter {
+        protocol: String,
+        upper_bound: Decimal,
+    },
+    RegisterChangeLimiter {
+        protocol: String,
+        boundary_offset: Decimal,
+        window_size: u64,
+        division_count: u64,
+    },
+    DeregisterLimiter {
+        protocol: String,
+    },
+
+    // Private-query authentication
+    SetViewingKey {
+        key: String,
+    },
+    CreateViewingKey {
+        entropy: String,
+    },
+
+    // Contract-wide killswitch
+    SetContractStatus {
+        status: ContractStatus,
+        // Why the status is changing, so `GetContractStatus` gives an
+        // operator reading it back later the incident context rather than a
+        // bare enum.
+        reason: String,
+    },
+
+    // Refreshes `ProtocolInfo.current_balance` and `TOTAL_USDC_VALUE` from
+    // each enabled protocol's live on-chain balance. Restricted to
+    // `Role::Rebalancer` (or the admin).
+    SyncBalances {},
+
+    // Executes whatever `GetRebalancePlan` currently computes: pulls every
+    // drifted protocol back toward its stored `allocation_percentage`.
+    // Restricted to `Role::Rebalancer` (or the admin).
+    AutoRebalance {},
+
+    // Claims every enabled protocol's pending rewards via its adapter,
+    // decoupled from deposit/withdraw/rebalance. If `compound` is true,
+    // redeposits the harvested total back into protocols per their current
+    // allocation instead of leaving it idle in the vault's balance.
+    // Restricted to `Role::Rebalancer` (or the admin), same as `Rebalance`.
+    HarvestRewards { compound: Option<bool> },
+
+    // Role-based permission control
+    GrantRole {
+        address: String,
+        role: Role,
+    },
+    RevokeRole {
+        address: String,
+        role: Role,
+    },
+}
+
+// Payload a cw20 `Send` call wraps its hook message in, decoded from
+// `Cw20ReceiveMsg.msg` by `ExecuteMsg::Receive`.
+#[cw_serde]
+pub enum Cw20HookMsg {
+    // Mirrors `ExecuteMsg::Deposit`: credits `recipient` (or the cw20
+    // `Send`'s original sender if `None`) with the sent amount's USDC value.
+    Deposit { recipient: Option<String> },
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
+    // Public: returns only the aggregate `shares`/`asset_value`/`cost_basis`,
+    // with `deposits` always empty. The per-deposit breakdown requires
+    // `GetUserTxHistory` (viewing key) or `WithPermit` (signed permit).
     #[returns(GetUserInfoResponse)]
     GetUserInfo { address: String },
 
+    // Just the vault shares an address holds, without the rest of
+    // `GetUserInfo`'s aggregate. Same public aggregate-only visibility.
+    #[returns(GetSharesResponse)]
+    GetShares { address: String },
+
+    // The vault-wide share price inputs: total shares outstanding and the
+    // total assets they're redeemable against. `GetUserInfo`/`GetShares`
+    // derive one address's `asset_value` from these same two numbers.
+    #[returns(GetShareValueResponse)]
+    GetShareValue {},
+
+    #[returns(GetUserTxHistoryResponse)]
+    GetUserTxHistory {
+        address: String,
+        key: Option<String>,
+    },
+
+    #[returns(PermitQueryResponse)]
+    WithPermit {
+        permit: QueryPermit,
+        query: PermitQuery,
+    },
+
     #[returns(GetProtocolsResponse)]
     GetProtocols {},
 
@@ -84,19 +305,145 @@ pub enum QueryMsg {
     #[returns(GetRiskParametersResponse)]
     GetRiskParameters {},
 
+    // Cursor-paginated, newest-first: pass the last entry's `seq` back in
+    // as `start_after` to fetch the next page.
     #[returns(GetRebalanceHistoryResponse)]
-    GetRebalanceHistory { limit: Option<u32> },
+    GetRebalanceHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Cursor-paginated, newest-first, same convention as `GetRebalanceHistory`.
+    #[returns(GetHarvestHistoryResponse)]
+    GetHarvestHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 
     #[returns(GetTotalValueResponse)]
     GetTotalValue {},
 
     #[returns(Config)]
     GetConfig {},
+
+    #[returns(ContractStatusInfo)]
+    GetContractStatus {},
+
+    #[returns(GetClaimsResponse)]
+    GetClaims { address: String },
+
+    #[returns(ReconcileTotalValueResponse)]
+    ReconcileTotalValue {},
+
+    // Performance fee collected and not yet paid out by `ClaimFees`.
+    #[returns(GetAccruedFeesResponse)]
+    GetAccruedFees {},
+
+    // The weighted split of the vault-wide performance fee set by
+    // `SetFeeRecipients`, empty until it's ever been called.
+    #[returns(GetFeeRecipientsResponse)]
+    GetFeeRecipients {},
+
+    // The rate and split `UpdateFeeConfig`/`SetFeeRecipients` currently have
+    // configured, bundled into a single response for a UI to render as one
+    // settings screen.
+    #[returns(GetFeeConfigResponse)]
+    GetFeeConfig {},
+
+    // The Astroport pair contract registered for `denom_a`/`denom_b` via
+    // `RegisterPair`, if any; `None` means conversions fall back to the router.
+    #[returns(GetPairContractResponse)]
+    GetPairContract { denom_a: String, denom_b: String },
+
+    #[returns(GetDepositQuoteResponse)]
+    GetDepositQuote { denom: String, amount: Uint128 },
+
+    #[returns(GetProtocolBalancesResponse)]
+    GetProtocolBalances {},
+
+    // Drift-triggered moves `AutoRebalance` would execute right now: only
+    // protocols whose live weight (`current_balance / TOTAL_USDC_VALUE`)
+    // deviates from their target `allocation_percentage` by more than
+    // `RiskParameters.rebalance_threshold`.
+    #[returns(GetRebalancePlanResponse)]
+    GetRebalancePlan {},
+
+    // Every denom with a registered Pyth price feed, plus the oracle
+    // contract and staleness bound they're checked against.
+    #[returns(GetPriceFeedsResponse)]
+    GetPriceFeeds {},
+
+    // Roles granted to `address` on top of the root admin/ai_operator,
+    // empty for an address that's never been granted one.
+    #[returns(GetRolesResponse)]
+    GetRoles { address: String },
+
+    // Previews the withdrawals/deposits `Rebalance` would execute to
+    // reach `target_allocations`, same as `GetRebalancePlan`'s move list but
+    // for an arbitrary target rather than the threshold-triggered one. Legs
+    // against an `astroport_amm` protocol also carry the swap's locally
+    // computed `expected_out`/`min_receive`; other protocols report `None`
+    // for both since they don't swap.
+    #[returns(GetRebalanceSimulationResponse)]
+    SimulateRebalance {
+        target_allocations: Vec<(String, Decimal)>,
+    },
+
+    // Cursor-paginated, newest-first transaction ledger for one address:
+    // every `Deposit`/`Withdraw`/`Rebalance`/`EmergencyWithdraw` event it was
+    // the actor of. Same `start_after`/`limit` cursor as `GetRebalanceHistory`.
+    #[returns(GetUserTransactionsResponse)]
+    GetUserTransactions {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // The manipulation-resistant cumulative-price average for `denom` since
+    // it was first observed, averaged over at least `window_secs` of
+    // history. `has_sufficient_data` is false (and `twap_price` is zero)
+    // if `denom` hasn't been observed yet or fewer than `window_secs` have
+    // elapsed since its first observation.
+    #[returns(GetTwapPriceResponse)]
+    GetTwapPrice { denom: String, window_secs: u64 },
 }
 
 #[cw_serde]
 pub struct GetUserInfoResponse {
     pub user_info: UserInfo,
+    // Vault shares the address currently holds and their redemption value
+    // in asset terms (`shares * total_assets / total_shares`), computed
+    // fresh at query time rather than stored on `UserInfo` since both move
+    // with every other depositor's activity, not just this address's own.
+    pub shares: Uint128,
+    pub asset_value: Uint128,
+}
+
+#[cw_serde]
+pub struct GetSharesResponse {
+    pub shares: Uint128,
+}
+
+#[cw_serde]
+pub struct GetShareValueResponse {
+    pub total_shares: Uint128,
+    pub total_assets: Uint128,
+}
+
+#[cw_serde]
+pub struct GetUserTxHistoryResponse {
+    pub deposits: Vec<UserDeposit>,
+}
+
+#[cw_serde]
+pub enum PermitQueryResponse {
+    UserInfo(GetUserInfoResponse),
+    TxHistory(GetUserTxHistoryResponse),
+}
+
+#[cw_serde]
+pub struct CreateViewingKeyResponse {
+    pub key: String,
 }
 
 #[cw_serde]
@@ -116,7 +463,17 @@ pub struct GetRiskParametersResponse {
 
 #[cw_serde]
 pub struct GetRebalanceHistoryResponse {
-    pub history: Vec<RebalanceRecord>,
+    pub history: Vec<TxRecord>,
+}
+
+#[cw_serde]
+pub struct GetHarvestHistoryResponse {
+    pub history: Vec<TxRecord>,
+}
+
+#[cw_serde]
+pub struct GetUserTransactionsResponse {
+    pub transactions: Vec<TxRecord>,
 }
 
 #[cw_serde]
@@ -124,11 +481,133 @@ pub struct GetTotalValueResponse {
     pub total_value: Uint128,
 }
 
+#[cw_serde]
+pub struct GetClaimsResponse {
+    pub claims: Vec<Claim>,
+}
+
+#[cw_serde]
+pub struct ProtocolBalanceSnapshot {
+    pub name: String,
+    pub stored_balance: Uint128,
+    pub live_balance: Uint128,
+}
+
+#[cw_serde]
+pub struct ReconcileTotalValueResponse {
+    pub stored_total: Uint128,
+    pub live_total: Uint128,
+    pub drift: Uint128,
+    pub live_exceeds_stored: bool,
+    pub protocols: Vec<ProtocolBalanceSnapshot>,
+}
+
+#[cw_serde]
+pub struct ProtocolBalance {
+    pub name: String,
+    // `ProtocolInfo.current_balance` as last written by a deposit, withdrawal,
+    // rebalance, or `SyncBalances`, side by side with what the adapter
+    // reports right now so an operator can see drift without a second call.
+    pub stored_balance: Uint128,
+    pub live_balance: Uint128,
+}
+
+#[cw_serde]
+pub struct GetProtocolBalancesResponse {
+    pub protocols: Vec<ProtocolBalance>,
+    pub total: Uint128,
+}
+
+#[cw_serde]
+pub struct RebalancePlanMove {
+    pub protocol: String,
+    pub current_weight: Decimal,
+    pub target_weight: Decimal,
+    pub drift: Decimal,
+    pub withdraw_amount: Uint128,
+    pub deposit_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct GetRebalancePlanResponse {
+    pub moves: Vec<RebalancePlanMove>,
+    pub reason: String,
+}
+
 #[cw_serde]
 pub struct Config {
     pub admin: Addr,
     pub ai_operator: Addr,
     pub base_denom: String,
-    pub accepted_denoms: Vec<String>,
+    pub accepted_denoms: Vec<AssetInfo>,
     pub astroport_router: String,
+    pub unbonding_period: Option<u64>,
+    pub performance_fee_bps: u16,
+    pub fee_collector: Addr,
+    pub pending_admin: Option<Addr>,
+    pub pending_ai_operator: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct GetAccruedFeesResponse {
+    pub accrued_fees: Uint128,
+}
+
+#[cw_serde]
+pub struct GetFeeRecipientsResponse {
+    pub recipients: Vec<(Addr, Decimal)>,
+}
+
+#[cw_serde]
+pub struct GetFeeConfigResponse {
+    pub performance_fee: Decimal,
+    pub recipients: Vec<(Addr, Decimal)>,
+}
+
+#[cw_serde]
+pub struct GetPairContractResponse {
+    pub pair_contract: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct GetDepositQuoteResponse {
+    pub usdc_value: Uint128,
+}
+
+#[cw_serde]
+pub struct PriceFeedEntry {
+    pub denom: String,
+    pub feed_id: Binary,
+}
+
+#[cw_serde]
+pub struct GetPriceFeedsResponse {
+    pub oracle_addr: Option<Addr>,
+    pub max_staleness: u64,
+    pub feeds: Vec<PriceFeedEntry>,
+}
+
+#[cw_serde]
+pub struct RebalanceSimulationLeg {
+    pub protocol: String,
+    pub action: String,
+    pub amount: Uint128,
+    pub expected_out: Option<Uint128>,
+    pub min_receive: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct GetRebalanceSimulationResponse {
+    pub legs: Vec<RebalanceSimulationLeg>,
+}
+
+#[cw_serde]
+pub struct GetRolesResponse {
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct GetTwapPriceResponse {
+    pub twap_price: Decimal,
+    pub has_sufficient_data: bool,
 }
@@ -0,0 +1,236 @@
+use crate::error::ContractError;
+use cosmwasm_std::{Addr, Binary, Decimal, Deps, Timestamp, Uint128};
+
+/// Minimal Pyth price-feed interface. In production this would come from the
+/// `pyth-sdk-cw` crate; we model just the shape we query against here, the
+/// same way `protocols.rs` models the Helix/Hydro/Neptune interfaces inline.
+pub mod pyth {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::Binary;
+
+    #[cw_serde]
+    pub enum QueryMsg {
+        PriceFeed { id: Binary },
+    }
+
+    #[cw_serde]
+    pub struct PriceFeedResponse {
+        pub price_feed: PriceFeed,
+    }
+
+    #[cw_serde]
+    pub struct PriceFeed {
+        pub id: Binary,
+        pub price: Price,
+        pub ema_price: Price,
+    }
+
+    #[cw_serde]
+    pub struct Price {
+        pub price: i64,
+        pub conf: u64,
+        pub expo: i32,
+        pub publish_time: i64,
+    }
+
+    impl PriceFeed {
+        pub fn get_price_unchecked(&self) -> Price {
+            self.price
+        }
+
+        /// Mirrors `pyth_sdk_cw::PriceFeed::get_ema_price_no_older_than`:
+        /// returns the EMA price only if it was published within `max_age`
+        /// seconds of `current_time`.
+        pub fn get_ema_price_no_older_than(
+            &self,
+            current_time: i64,
+            max_age: u64,
+        ) -> Option<Price> {
+            if current_time.saturating_sub(self.ema_price.publish_time) <= max_age as i64 {
+                Some(self.ema_price)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Normalize a Pyth `(price, expo)` pair into a `Decimal`, conservatively
+/// discounting it by the reported confidence interval so the value credited
+/// to a user is never more favorable than the true price could be.
+pub fn conservative_price(price: &pyth::Price) -> Result<Decimal, ContractError> {
+    if price.price <= 0 {
+        return Err(ContractError::InvalidPrice {});
+    }
+
+    let discounted = (price.price as u64).saturating_sub(price.conf);
+    if discounted == 0 {
+        return Err(ContractError::InvalidPrice {});
+    }
+
+    scale_by_expo(discounted, price.expo)
+}
+
+/// Apply a Pyth-style signed decimal exponent: `value = mantissa * 10^expo`.
+fn scale_by_expo(mantissa: u64, expo: i32) -> Result<Decimal, ContractError> {
+    let mantissa = Decimal::from_ratio(mantissa, 1u128);
+
+    if expo >= 0 {
+        let scale = 10u128
+            .checked_pow(expo as u32)
+            .ok_or(ContractError::InvalidPrice {})?;
+        Ok(mantissa * Decimal::from_ratio(scale, 1u128))
+    } else {
+        let scale = 10u128
+            .checked_pow((-expo) as u32)
+            .ok_or(ContractError::InvalidPrice {})?;
+        Ok(mantissa / Decimal::from_ratio(scale, 1u128))
+    }
+}
+
+/// Query the Pyth contract at `oracle_addr` for `feed_id`, preferring the EMA
+/// price when it is fresh enough and otherwise falling back to the spot
+/// price, rejecting both if they are older than `max_staleness` seconds.
+pub fn query_validated_price(
+    deps: Deps,
+    oracle_addr: &Addr,
+    feed_id: &Binary,
+    now: Timestamp,
+    max_staleness: u64,
+) -> Result<Decimal, ContractError> {
+    let response: pyth::PriceFeedResponse = deps.querier.query_wasm_smart(
+        oracle_addr,
+        &pyth::QueryMsg::PriceFeed {
+            id: feed_id.clone(),
+        },
+    )?;
+
+    let now_secs = now.seconds() as i64;
+
+    let price = match response
+        .price_feed
+        .get_ema_price_no_older_than(now_secs, max_staleness)
+    {
+        Some(ema) => ema,
+        None => {
+            let spot = response.price_feed.get_price_unchecked();
+            if now_secs.saturating_sub(spot.publish_time) > max_staleness as i64 {
+                return Err(ContractError::StalePrice {
+                    publish_time: spot.publish_time,
+                    now: now_secs,
+                });
+            }
+            spot
+        }
+    };
+
+    conservative_price(&price)
+}
+
+/// Values `amount` of a token in USD terms for crediting a deposit, gating
+/// both the spot and EMA price on `max_staleness` and taking the lower of
+/// the two resulting valuations so a single manipulated tick (spot or EMA)
+/// can't inflate what gets credited.
+pub fn query_conservative_deposit_value(
+    deps: Deps,
+    oracle_addr: &Addr,
+    feed_id: &Binary,
+    amount: Uint128,
+    now: Timestamp,
+    max_staleness: u64,
+) -> Result<Uint128, ContractError> {
+    let response: pyth::PriceFeedResponse = deps.querier.query_wasm_smart(
+        oracle_addr,
+        &pyth::QueryMsg::PriceFeed {
+            id: feed_id.clone(),
+        },
+    )?;
+
+    let now_secs = now.seconds() as i64;
+
+    let spot = response.price_feed.get_price_unchecked();
+    if now_secs.saturating_sub(spot.publish_time) > max_staleness as i64 {
+        return Err(ContractError::StalePrice {
+            publish_time: spot.publish_time,
+            now: now_secs,
+        });
+    }
+    let ema = response
+        .price_feed
+        .get_ema_price_no_older_than(now_secs, max_staleness)
+        .ok_or(ContractError::StalePrice {
+            publish_time: response.price_feed.ema_price.publish_time,
+            now: now_secs,
+        })?;
+
+    let spot_price = conservative_price(&spot)?;
+    let ema_price = conservative_price(&ema)?;
+    let lowest_price = spot_price.min(ema_price);
+
+    Ok(amount.multiply_ratio(lowest_price.numerator(), lowest_price.denominator()))
+}
+
+/// Values `balance` of a token already priced in USD terms by `price`,
+/// letting callers normalize a protocol holding to the base denom before
+/// comparing it against other protocols' balances (e.g. during rebalance
+/// drift calculations).
+pub fn value_in_base(balance: Uint128, price: Decimal) -> Uint128 {
+    balance.multiply_ratio(price.numerator(), price.denominator())
+}
+
+/// Convert `amount` of a source denom into a target denom using two prices
+/// already quoted in a common USD base.
+pub fn convert_amount(
+    amount: Uint128,
+    price_from: Decimal,
+    price_to: Decimal,
+) -> Result<Uint128, ContractError> {
+    if price_to.is_zero() {
+        return Err(ContractError::InvalidPrice {});
+    }
+
+    let usd_value = amount.multiply_ratio(price_from.numerator(), price_from.denominator());
+    Ok(usd_value.multiply_ratio(price_to.denominator(), price_to.numerator()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_positive_and_negative_exponents() {
+        // 12345 * 10^-2 = 123.45
+        assert_eq!(
+            scale_by_expo(12345, -2).unwrap(),
+            Decimal::from_ratio(12345u128, 100u128)
+        );
+        // 5 * 10^2 = 500
+        assert_eq!(scale_by_expo(5, 2).unwrap(), Decimal::from_ratio(500u128, 1u128));
+    }
+
+    #[test]
+    fn conservative_price_subtracts_confidence() {
+        let price = pyth::Price {
+            price: 1_000_000,
+            conf: 1_000,
+            expo: -6,
+            publish_time: 0,
+        };
+        // (1_000_000 - 1_000) * 10^-6 = 0.999
+        assert_eq!(
+            conservative_price(&price).unwrap(),
+            Decimal::from_ratio(999_000u128, 1_000_000u128)
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_price() {
+        let price = pyth::Price {
+            price: 0,
+            conf: 0,
+            expo: -6,
+            publish_time: 0,
+        };
+        assert_eq!(conservative_price(&price), Err(ContractError::InvalidPrice {}));
+    }
+}
@@ -0,0 +1,152 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Deps};
+use cw_storage_plus::Map;
+
+use crate::error::ContractError;
+use crate::state::CONFIG;
+
+/// A grantable authority over a slice of admin-gated operations, layered on
+/// top of (not replacing) the root `Config::admin`. `Role::Admin` passes
+/// every `require_role` check, same as the root admin; the other roles only
+/// authorize their own slice. This lets the contract delegate
+/// `execute_rebalance` to multiple operator keys or `execute_update_risk_parameters`
+/// to a separate risk committee without ever handing out the root admin key.
+#[cw_serde]
+#[derive(Eq, Copy)]
+pub enum Role {
+    Admin,
+    ProtocolManager,
+    ParamManager,
+    Rebalancer,
+}
+
+pub const ROLES: Map<&Addr, Vec<Role>> = Map::new("roles");
+
+/// Authorizes `sender` for `role`: the config admin and any address holding
+/// `Role::Admin` pass for every role, otherwise `sender` must hold `role`
+/// itself.
+pub fn require_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if sender == &config.admin {
+        return Ok(());
+    }
+
+    let roles = ROLES.may_load(deps.storage, sender)?.unwrap_or_default();
+    if roles.contains(&Role::Admin) || roles.contains(&role) {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+/// Adds `role` to `address`'s grants, a no-op if already held.
+pub fn grant_role(
+    storage: &mut dyn cosmwasm_std::Storage,
+    address: &Addr,
+    role: Role,
+) -> Result<(), ContractError> {
+    ROLES.update(storage, address, |maybe_roles| -> Result<_, ContractError> {
+        let mut roles = maybe_roles.unwrap_or_default();
+        if !roles.contains(&role) {
+            roles.push(role);
+        }
+        Ok(roles)
+    })?;
+    Ok(())
+}
+
+/// Removes `role` from `address`'s grants, a no-op if not held.
+pub fn revoke_role(
+    storage: &mut dyn cosmwasm_std::Storage,
+    address: &Addr,
+    role: Role,
+) -> Result<(), ContractError> {
+    ROLES.update(storage, address, |maybe_roles| -> Result<_, ContractError> {
+        let mut roles = maybe_roles.unwrap_or_default();
+        roles.retain(|r| r != &role);
+        Ok(roles)
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+    use crate::state::{AssetInfo, Config};
+
+    fn save_config(storage: &mut dyn cosmwasm_std::Storage, admin: &Addr) {
+        CONFIG
+            .save(
+                storage,
+                &Config {
+                    admin: admin.clone(),
+                    ai_operator: Addr::unchecked("operator"),
+                    base_denom: "usdc".to_string(),
+                    accepted_denoms: vec![AssetInfo::Native("usdc".to_string())],
+                    astroport_router: "router".to_string(),
+                    unbonding_period: None,
+                    performance_fee_bps: 0,
+                    fee_collector: admin.clone(),
+                    pending_admin: None,
+                    pending_ai_operator: None,
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn root_admin_passes_every_role() {
+        let mut deps = mock_dependencies();
+        let admin = Addr::unchecked("admin");
+        save_config(&mut deps.storage, &admin);
+
+        assert!(require_role(deps.as_ref(), &admin, Role::ProtocolManager).is_ok());
+        assert!(require_role(deps.as_ref(), &admin, Role::Rebalancer).is_ok());
+    }
+
+    #[test]
+    fn unrelated_role_is_rejected() {
+        let mut deps = mock_dependencies();
+        let admin = Addr::unchecked("admin");
+        save_config(&mut deps.storage, &admin);
+
+        let committee = Addr::unchecked("committee");
+        grant_role(&mut deps.storage, &committee, Role::ParamManager).unwrap();
+
+        assert!(require_role(deps.as_ref(), &committee, Role::ParamManager).is_ok());
+        assert_eq!(
+            require_role(deps.as_ref(), &committee, Role::Rebalancer),
+            Err(ContractError::Unauthorized {})
+        );
+    }
+
+    #[test]
+    fn granted_admin_role_passes_every_check() {
+        let mut deps = mock_dependencies();
+        let admin = Addr::unchecked("admin");
+        save_config(&mut deps.storage, &admin);
+
+        let delegate = Addr::unchecked("delegate");
+        grant_role(&mut deps.storage, &delegate, Role::Admin).unwrap();
+
+        assert!(require_role(deps.as_ref(), &delegate, Role::ProtocolManager).is_ok());
+    }
+
+    #[test]
+    fn revoke_role_removes_authorization() {
+        let mut deps = mock_dependencies();
+        let admin = Addr::unchecked("admin");
+        save_config(&mut deps.storage, &admin);
+
+        let committee = Addr::unchecked("committee");
+        grant_role(&mut deps.storage, &committee, Role::Rebalancer).unwrap();
+        assert!(require_role(deps.as_ref(), &committee, Role::Rebalancer).is_ok());
+
+        revoke_role(&mut deps.storage, &committee, Role::Rebalancer).unwrap();
+        assert_eq!(
+            require_role(deps.as_ref(), &committee, Role::Rebalancer),
+            Err(ContractError::Unauthorized {})
+        );
+    }
+}
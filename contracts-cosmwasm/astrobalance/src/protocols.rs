@@ -1,25 +1,81 @@
 use crate::error::ContractError;
+use crate::oracle;
+use crate::state::{AssetInfo, AMM_LP_SHARES, ORACLE_ADDR, PRICE_FEED_IDS, RISK_PARAMETERS};
+use crate::swap_math;
 use cosmwasm_std::{
-    to_json_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, StdError, StdResult,
-    Uint128, WasmMsg,
+    to_json_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, StdError,
+    StdResult, Uint128, Uint256, WasmMsg,
 };
 
+/// Builds the message(s) that hand `amount` of `asset` to `contract_addr`
+/// alongside `inner_msg`: attached as native `funds` for `AssetInfo::Native`,
+/// or wrapped in a `Cw20ExecuteMsg::Send` hook for `AssetInfo::Cw20` (the
+/// standard way a cw20 token routes a deposit call together with the
+/// payment, since cw20 tokens can't be attached as `funds`).
+fn route_deposit(
+    asset: &AssetInfo,
+    contract_addr: &Addr,
+    amount: Uint128,
+    inner_msg: Binary,
+) -> Result<Vec<CosmosMsg>, StdError> {
+    let msg = match asset {
+        AssetInfo::Native(denom) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: inner_msg,
+            funds: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }),
+        AssetInfo::Cw20(cw20_addr) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: cw20_addr.to_string(),
+            msg: to_json_binary(&cw20::Cw20ExecuteMsg::Send {
+                contract: contract_addr.to_string(),
+                amount,
+                msg: inner_msg,
+            })?,
+            funds: vec![],
+        }),
+    };
+
+    Ok(vec![msg])
+}
+
 /// Trait defining standard interface for all protocol adapters
 pub trait YieldProtocol {
-    fn deposit(&self, deps: DepsMut, env: Env, amount: Uint128)
-        -> Result<Vec<CosmosMsg>, StdError>;
+    // `min_out` floors what the underlying protocol must credit back for
+    // this call to succeed, per `RebalanceAction.min_out`. A lending/staking
+    // adapter that moves 1:1 has nothing to float against and ignores it;
+    // `AstroportAmmAdapter` already enforces its own finer-grained guard
+    // internally and ignores it too.
+    fn deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        amount: Uint128,
+        min_out: Uint128,
+    ) -> Result<Vec<CosmosMsg>, StdError>;
 
     fn withdraw(
         &self,
         _deps: DepsMut,
         env: Env,
         amount: Uint128,
+        min_out: Uint128,
     ) -> Result<Vec<CosmosMsg>, StdError>;
 
     fn query_balance(&self, deps: Deps, env: Env) -> StdResult<Uint128>;
 
     fn query_apy(&self, deps: Deps, env: Env) -> StdResult<Decimal>;
 
+    // Messages that claim this protocol's pending rewards, separate from
+    // `withdraw` so harvesting never touches principal. Defaults to no
+    // claimable rewards; an adapter whose protocol pays out separately from
+    // `query_balance` overrides this.
+    fn claim_rewards(&self, _deps: DepsMut, _env: Env) -> Result<Vec<CosmosMsg>, StdError> {
+        Ok(vec![])
+    }
+
     fn name(&self) -> &str;
 
     fn protocol_type(&self) -> &str;
@@ -31,6 +87,7 @@ pub trait YieldProtocol {
 pub struct HelixAdapter {
     pub contract_addr: Addr,
     pub name: String,
+    pub deposit_asset: AssetInfo,
 }
 
 impl YieldProtocol for HelixAdapter {
@@ -39,18 +96,16 @@ impl YieldProtocol for HelixAdapter {
         _deps: DepsMut,
         _env: Env,
         amount: Uint128,
+        _min_out: Uint128,
     ) -> Result<Vec<CosmosMsg>, StdError> {
-        // Implementation for Helix deposit
-        let msg = WasmMsg::Execute {
-            contract_addr: self.contract_addr.to_string(),
-            msg: to_json_binary(&helix::ExecuteMsg::Deposit {})?,
-            funds: vec![Coin {
-                denom: "usdc".to_string(),
-                amount,
-            }],
-        };
-
-        Ok(vec![CosmosMsg::Wasm(msg)])
+        // Implementation for Helix deposit. A Helix deposit is 1:1 into the
+        // lending position, so there's no exchange rate for `min_out` to floor.
+        route_deposit(
+            &self.deposit_asset,
+            &self.contract_addr,
+            amount,
+            to_json_binary(&helix::ExecuteMsg::Deposit {})?,
+        )
     }
 
     fn withdraw(
@@ -58,11 +113,12 @@ impl YieldProtocol for HelixAdapter {
         _deps: DepsMut,
         _env: Env,
         amount: Uint128,
+        min_out: Uint128,
     ) -> Result<Vec<CosmosMsg>, StdError> {
         // Implementation for Helix withdraw
         let msg = WasmMsg::Execute {
             contract_addr: self.contract_addr.to_string(),
-            msg: to_json_binary(&helix::ExecuteMsg::Withdraw { amount })?,
+            msg: to_json_binary(&helix::ExecuteMsg::Withdraw { amount, min_out })?,
             funds: vec![],
         };
 
@@ -90,6 +146,17 @@ impl YieldProtocol for HelixAdapter {
         Ok(apy.apy)
     }
 
+    fn claim_rewards(&self, _deps: DepsMut, _env: Env) -> Result<Vec<CosmosMsg>, StdError> {
+        // Implementation for Helix reward claim
+        let msg = WasmMsg::Execute {
+            contract_addr: self.contract_addr.to_string(),
+            msg: to_json_binary(&helix::ExecuteMsg::ClaimRewards {})?,
+            funds: vec![],
+        };
+
+        Ok(vec![CosmosMsg::Wasm(msg)])
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -103,6 +170,7 @@ impl YieldProtocol for HelixAdapter {
 pub struct HydroAdapter {
     pub contract_addr: Addr,
     pub name: String,
+    pub deposit_asset: AssetInfo,
 }
 
 impl YieldProtocol for HydroAdapter {
@@ -111,18 +179,17 @@ impl YieldProtocol for HydroAdapter {
         _deps: DepsMut,
         _env: Env,
         amount: Uint128,
+        _min_out: Uint128,
     ) -> Result<Vec<CosmosMsg>, StdError> {
-        // Implementation for Hydro deposit - lending pool
-        let msg = WasmMsg::Execute {
-            contract_addr: self.contract_addr.to_string(),
-            msg: to_json_binary(&hydro::ExecuteMsg::SupplyLiquidity {})?,
-            funds: vec![Coin {
-                denom: "usdc".to_string(),
-                amount,
-            }],
-        };
-
-        Ok(vec![CosmosMsg::Wasm(msg)])
+        // Implementation for Hydro deposit - lending pool. Supplying
+        // liquidity is 1:1 into the pool's accounting, so there's no
+        // exchange rate for `min_out` to floor.
+        route_deposit(
+            &self.deposit_asset,
+            &self.contract_addr,
+            amount,
+            to_json_binary(&hydro::ExecuteMsg::SupplyLiquidity {})?,
+        )
     }
 
     fn withdraw(
@@ -130,11 +197,12 @@ impl YieldProtocol for HydroAdapter {
         _deps: DepsMut,
         _env: Env,
         amount: Uint128,
+        min_out: Uint128,
     ) -> Result<Vec<CosmosMsg>, StdError> {
         // Implementation for Hydro withdraw
         let msg = WasmMsg::Execute {
             contract_addr: self.contract_addr.to_string(),
-            msg: to_json_binary(&hydro::ExecuteMsg::WithdrawLiquidity { amount })?,
+            msg: to_json_binary(&hydro::ExecuteMsg::WithdrawLiquidity { amount, min_out })?,
             funds: vec![],
         };
 
@@ -163,6 +231,17 @@ impl YieldProtocol for HydroAdapter {
         Ok(apy.rate)
     }
 
+    fn claim_rewards(&self, _deps: DepsMut, _env: Env) -> Result<Vec<CosmosMsg>, StdError> {
+        // Implementation for Hydro reward claim
+        let msg = WasmMsg::Execute {
+            contract_addr: self.contract_addr.to_string(),
+            msg: to_json_binary(&hydro::ExecuteMsg::ClaimRewards {})?,
+            funds: vec![],
+        };
+
+        Ok(vec![CosmosMsg::Wasm(msg)])
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -176,6 +255,7 @@ impl YieldProtocol for HydroAdapter {
 pub struct NeptuneAdapter {
     pub contract_addr: Addr,
     pub name: String,
+    pub deposit_asset: AssetInfo,
 }
 
 impl YieldProtocol for NeptuneAdapter {
@@ -184,18 +264,16 @@ impl YieldProtocol for NeptuneAdapter {
         _deps: DepsMut,
         _env: Env,
         amount: Uint128,
+        _min_out: Uint128,
     ) -> Result<Vec<CosmosMsg>, StdError> {
-        // Implementation for Neptune staking
-        let msg = WasmMsg::Execute {
-            contract_addr: self.contract_addr.to_string(),
-            msg: to_json_binary(&neptune::ExecuteMsg::Stake {})?,
-            funds: vec![Coin {
-                denom: "usdc".to_string(),
-                amount,
-            }],
-        };
-
-        Ok(vec![CosmosMsg::Wasm(msg)])
+        // Implementation for Neptune staking. Staking is 1:1 into the
+        // position, so there's no exchange rate for `min_out` to floor.
+        route_deposit(
+            &self.deposit_asset,
+            &self.contract_addr,
+            amount,
+            to_json_binary(&neptune::ExecuteMsg::Stake {})?,
+        )
     }
 
     fn withdraw(
@@ -203,11 +281,12 @@ impl YieldProtocol for NeptuneAdapter {
         _deps: DepsMut,
         _env: Env,
         amount: Uint128,
+        min_out: Uint128,
     ) -> Result<Vec<CosmosMsg>, StdError> {
         // Implementation for Neptune unstake
         let msg = WasmMsg::Execute {
             contract_addr: self.contract_addr.to_string(),
-            msg: to_json_binary(&neptune::ExecuteMsg::Unstake { amount })?,
+            msg: to_json_binary(&neptune::ExecuteMsg::Unstake { amount, min_out })?,
             funds: vec![],
         };
 
@@ -236,6 +315,17 @@ impl YieldProtocol for NeptuneAdapter {
         Ok(apy.apy)
     }
 
+    fn claim_rewards(&self, _deps: DepsMut, _env: Env) -> Result<Vec<CosmosMsg>, StdError> {
+        // Implementation for Neptune reward claim
+        let msg = WasmMsg::Execute {
+            contract_addr: self.contract_addr.to_string(),
+            msg: to_json_binary(&neptune::ExecuteMsg::ClaimRewards {})?,
+            funds: vec![],
+        };
+
+        Ok(vec![CosmosMsg::Wasm(msg)])
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -245,7 +335,504 @@ impl YieldProtocol for NeptuneAdapter {
     }
 }
 
+// Astroport-style constant-product AMM Adapter - Liquidity Provision
+//
+// Unlike the lending-style adapters above, a single-sided USDC `deposit`
+// can't be handed to the pool as-is: providing liquidity needs both sides
+// of the pair in the pool's current ratio. `deposit` swaps just enough USDC
+// for the paired asset first, then provides both; `withdraw` reverses that,
+// burning LP shares and swapping the paired side back to USDC. LP shares
+// held are tracked in `AMM_LP_SHARES` rather than `ProtocolInfo`, since a
+// lending-style `current_balance` doesn't capture a pool position.
+pub struct AstroportAmmAdapter {
+    pub contract_addr: Addr,
+    pub name: String,
+}
+
+impl AstroportAmmAdapter {
+    /// Reads the pool's current reserves, identifying which side is
+    /// `base_denom` ("usdc") and which is the paired asset.
+    fn query_reserves(
+        &self,
+        deps: Deps,
+    ) -> StdResult<(Uint128, Uint128, String, Uint128)> {
+        let pool: astroport_pair::PoolResponse = deps
+            .querier
+            .query_wasm_smart(self.contract_addr.to_string(), &astroport_pair::QueryMsg::Pool {})?;
+
+        let usdc_asset = pool
+            .assets
+            .iter()
+            .find(|asset| matches!(&asset.info, astroport_pair::AssetInfo::NativeToken { denom } if denom == "usdc"))
+            .ok_or_else(|| StdError::generic_err("AMM pool has no usdc side"))?;
+        let paired_asset = pool
+            .assets
+            .iter()
+            .find(|asset| !matches!(&asset.info, astroport_pair::AssetInfo::NativeToken { denom } if denom == "usdc"))
+            .ok_or_else(|| StdError::generic_err("AMM pool has no paired asset"))?;
+
+        let astroport_pair::AssetInfo::NativeToken { denom: paired_denom } = paired_asset.info.clone();
+
+        Ok((usdc_asset.amount, paired_asset.amount, paired_denom, pool.total_share))
+    }
+
+    /// Values the pool's paired-asset side via a registered Pyth feed when
+    /// one exists, so a de-pegged paired asset doesn't inflate the
+    /// adapter's reported balance. Falls back to the constant-product
+    /// assumption that both sides hold roughly equal USD value (arbitrage
+    /// keeps them in sync) when no feed is registered for `paired_denom`.
+    fn pool_total_value_usdc(
+        deps: Deps,
+        env: &Env,
+        usdc_reserve: Uint128,
+        paired_reserve: Uint128,
+        paired_denom: &str,
+    ) -> Result<Uint128, ContractError> {
+        let oracle_feed = ORACLE_ADDR.may_load(deps.storage)?.and_then(|oracle_addr| {
+            PRICE_FEED_IDS
+                .may_load(deps.storage, paired_denom)
+                .ok()
+                .flatten()
+                .map(|feed_id| (oracle_addr, feed_id))
+        });
+
+        match oracle_feed {
+            Some((oracle_addr, feed_id)) => {
+                let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+                let price = oracle::query_validated_price(
+                    deps,
+                    &oracle_addr,
+                    &feed_id,
+                    env.block.time,
+                    risk_parameters.max_price_staleness,
+                )?;
+                Ok(usdc_reserve + oracle::value_in_base(paired_reserve, price))
+            }
+            None => Ok(usdc_reserve.multiply_ratio(2u128, 1u128)),
+        }
+    }
+
+    /// This adapter's share of the pool's total value, per `pool_total_value_usdc`.
+    fn pool_value(
+        deps: Deps,
+        env: &Env,
+        usdc_reserve: Uint128,
+        paired_reserve: Uint128,
+        paired_denom: &str,
+        lp_shares: Uint128,
+        total_share: Uint128,
+    ) -> Result<Uint128, ContractError> {
+        if total_share.is_zero() {
+            return Ok(Uint128::zero());
+        }
+
+        let total_value =
+            Self::pool_total_value_usdc(deps, env, usdc_reserve, paired_reserve, paired_denom)?;
+        Ok(total_value.multiply_ratio(lp_shares, total_share))
+    }
+
+    /// The USDC amount to swap first so the remaining USDC/paired-token
+    /// split matches the pool's reserve ratio: `s = sqrt(r_x * (r_x + d)) -
+    /// r_x`. Shared by `deposit` and its `simulate_deposit` preview.
+    fn deposit_swap_amount(usdc_reserve: Uint128, amount: Uint128) -> StdResult<Uint128> {
+        let r_x = Uint256::from(usdc_reserve);
+        let d = Uint256::from(amount);
+        Uint128::try_from(isqrt(r_x.checked_mul(r_x + d)?) - r_x)
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    /// Expected output and slippage-floor minimum for swapping `amount_in`
+    /// into a pool holding `(reserve_in, reserve_out)`, computed locally from
+    /// already-queried reserves rather than trusting the pool's own
+    /// simulation query (which a manipulated or stale pool could misreport).
+    fn quote_swap(
+        reserve_in: Uint128,
+        reserve_out: Uint128,
+        amount_in: Uint128,
+        max_slippage: Decimal,
+    ) -> Result<(Uint128, Uint128), StdError> {
+        let expected_out = swap_math::expected_output(reserve_in, reserve_out, amount_in);
+        if expected_out.is_zero() {
+            return Err(ContractError::SlippageExceeded {}.into());
+        }
+        Ok((expected_out, swap_math::min_receive(expected_out, max_slippage)))
+    }
+
+    /// Previews the swap leg a `deposit` of `amount` would perform, without
+    /// executing anything. Returns `(expected_out, min_receive)`, both zero
+    /// if the pool can't currently support the deposit.
+    pub fn simulate_deposit(
+        &self,
+        deps: Deps,
+        amount: Uint128,
+        max_slippage: Decimal,
+    ) -> StdResult<(Uint128, Uint128)> {
+        if amount.is_zero() {
+            return Ok((Uint128::zero(), Uint128::zero()));
+        }
+
+        let (usdc_reserve, paired_reserve, _paired_denom, _total_share) =
+            self.query_reserves(deps)?;
+        if usdc_reserve.is_zero() || paired_reserve.is_zero() {
+            return Ok((Uint128::zero(), Uint128::zero()));
+        }
+
+        let swap_amount = Self::deposit_swap_amount(usdc_reserve, amount)?;
+        if swap_amount.is_zero() || swap_amount >= amount {
+            return Ok((Uint128::zero(), Uint128::zero()));
+        }
+
+        Self::quote_swap(usdc_reserve, paired_reserve, swap_amount, max_slippage)
+    }
+
+    /// Previews the swap leg a `withdraw` of `amount` would perform, without
+    /// executing anything. Returns `(expected_out, min_receive)`, both zero
+    /// if there's no position or nothing to swap.
+    pub fn simulate_withdraw(
+        &self,
+        deps: Deps,
+        env: &Env,
+        amount: Uint128,
+        max_slippage: Decimal,
+    ) -> StdResult<(Uint128, Uint128)> {
+        let lp_shares = AMM_LP_SHARES.may_load(deps.storage, &self.name)?.unwrap_or_default();
+        if lp_shares.is_zero() {
+            return Ok((Uint128::zero(), Uint128::zero()));
+        }
+
+        let (usdc_reserve, paired_reserve, paired_denom, total_share) = self.query_reserves(deps)?;
+        let total_value = Self::pool_value(
+            deps,
+            env,
+            usdc_reserve,
+            paired_reserve,
+            &paired_denom,
+            lp_shares,
+            total_share,
+        )?;
+        if total_value.is_zero() {
+            return Ok((Uint128::zero(), Uint128::zero()));
+        }
+
+        let shares_to_burn = lp_shares.multiply_ratio(amount.min(total_value), total_value);
+        let withdrawn_paired = paired_reserve.multiply_ratio(shares_to_burn, total_share);
+        if withdrawn_paired.is_zero() {
+            return Ok((Uint128::zero(), Uint128::zero()));
+        }
+
+        Self::quote_swap(paired_reserve, usdc_reserve, withdrawn_paired, max_slippage)
+    }
+}
+
+impl YieldProtocol for AstroportAmmAdapter {
+    fn deposit(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        amount: Uint128,
+        // Already guarded by this adapter's own `risk_parameters.max_slippage`
+        // check below, computed from the pool's actual reserves rather than
+        // the caller's outer USDC-denominated estimate, so the threaded-in
+        // floor would be redundant here.
+        _min_out: Uint128,
+    ) -> Result<Vec<CosmosMsg>, StdError> {
+        if amount.is_zero() {
+            return Ok(vec![]);
+        }
+
+        let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+        let (usdc_reserve, paired_reserve, paired_denom, total_share) =
+            self.query_reserves(deps.as_ref())?;
+
+        if usdc_reserve.is_zero() || paired_reserve.is_zero() {
+            return Err(StdError::generic_err(
+                "cannot bootstrap an empty AMM pool from a single-sided USDC deposit",
+            ));
+        }
+
+        // Solve for the USDC amount `s` to swap first so the remaining
+        // USDC/paired-token split matches the pool's reserve ratio:
+        // s = sqrt(r_x * (r_x + d)) - r_x.
+        let swap_amount = Self::deposit_swap_amount(usdc_reserve, amount)?;
+
+        if swap_amount.is_zero() || swap_amount >= amount {
+            return Err(StdError::generic_err(
+                "degenerate AMM deposit swap amount",
+            ));
+        }
+
+        // Expected output and minimum-receive are computed locally from the
+        // reserves already queried above (constant-product math), rather
+        // than trusted from the pool's own `Simulation` query, so the guard
+        // holds even against a pool that misreports its own simulation.
+        let (expected_out, min_expected) = Self::quote_swap(
+            usdc_reserve,
+            paired_reserve,
+            swap_amount,
+            risk_parameters.max_slippage,
+        )?;
+
+        // Value the expected paired-token output back in USDC terms using
+        // the pre-swap reserve ratio, and reject if the total implied
+        // deposit value has drifted from `amount` by more than
+        // `max_slippage` (a manipulated or illiquid pool).
+        let implied_paired_value = expected_out.multiply_ratio(usdc_reserve, paired_reserve);
+        let implied_value = (amount - swap_amount) + implied_paired_value;
+        let divergence = Decimal::from_ratio(implied_value.abs_diff(amount), amount);
+        if divergence > risk_parameters.max_slippage {
+            return Err(ContractError::SlippageExceeded {}.into());
+        }
+
+        let swap_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.contract_addr.to_string(),
+            msg: to_json_binary(&astroport_pair::ExecuteMsg::Swap {
+                offer_asset: astroport_pair::Asset {
+                    info: astroport_pair::AssetInfo::NativeToken {
+                        denom: "usdc".to_string(),
+                    },
+                    amount: swap_amount,
+                },
+                max_spread: Some(risk_parameters.max_slippage),
+                belief_price: Some(Decimal::from_ratio(usdc_reserve, paired_reserve)),
+            })?,
+            funds: vec![Coin {
+                denom: "usdc".to_string(),
+                amount: swap_amount,
+            }],
+        });
+
+        let usdc_remaining = amount - swap_amount;
+        let provide_liquidity_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.contract_addr.to_string(),
+            msg: to_json_binary(&astroport_pair::ExecuteMsg::ProvideLiquidity {
+                assets: vec![
+                    astroport_pair::Asset {
+                        info: astroport_pair::AssetInfo::NativeToken {
+                            denom: "usdc".to_string(),
+                        },
+                        amount: usdc_remaining,
+                    },
+                    astroport_pair::Asset {
+                        info: astroport_pair::AssetInfo::NativeToken {
+                            denom: paired_denom.clone(),
+                        },
+                        amount: min_expected,
+                    },
+                ],
+                slippage_tolerance: Some(risk_parameters.max_slippage),
+            })?,
+            funds: vec![
+                Coin {
+                    denom: "usdc".to_string(),
+                    amount: usdc_remaining,
+                },
+                Coin {
+                    denom: paired_denom,
+                    amount: min_expected,
+                },
+            ],
+        });
+
+        // New USDC reserve after the swap, used to size the LP shares this
+        // deposit mints: `minted = total_share * usdc_remaining /
+        // (r_x + swap_amount)`, the standard proportional-deposit formula.
+        let minted_shares = if total_share.is_zero() {
+            usdc_remaining + min_expected
+        } else {
+            total_share.multiply_ratio(usdc_remaining, usdc_reserve + swap_amount)
+        };
+
+        AMM_LP_SHARES.update(deps.storage, &self.name, |existing| -> StdResult<_> {
+            Ok(existing.unwrap_or_default() + minted_shares)
+        })?;
+
+        Ok(vec![swap_msg, provide_liquidity_msg])
+    }
+
+    fn withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        amount: Uint128,
+        // See `deposit`: this adapter's own reserve-derived `quote_swap`
+        // guard already supersedes a caller-supplied floor.
+        _min_out: Uint128,
+    ) -> Result<Vec<CosmosMsg>, StdError> {
+        if amount.is_zero() {
+            return Ok(vec![]);
+        }
+
+        let lp_shares = AMM_LP_SHARES
+            .may_load(deps.storage, &self.name)?
+            .unwrap_or_default();
+        if lp_shares.is_zero() {
+            return Err(StdError::generic_err("no AMM liquidity to withdraw"));
+        }
+
+        let risk_parameters = RISK_PARAMETERS.load(deps.storage)?;
+        let (usdc_reserve, paired_reserve, paired_denom, total_share) =
+            self.query_reserves(deps.as_ref())?;
+
+        let total_value = Self::pool_value(
+            deps.as_ref(),
+            &env,
+            usdc_reserve,
+            paired_reserve,
+            &paired_denom,
+            lp_shares,
+            total_share,
+        )?;
+        if total_value.is_zero() {
+            return Err(StdError::generic_err("AMM position has no redeemable value"));
+        }
+
+        // Burn the slice of this adapter's LP shares proportional to
+        // `amount`'s claim on the position's current value, capped at the
+        // full position.
+        let shares_to_burn = lp_shares.multiply_ratio(amount.min(total_value), total_value);
+
+        AMM_LP_SHARES.save(deps.storage, &self.name, &(lp_shares - shares_to_burn))?;
+
+        let withdraw_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.contract_addr.to_string(),
+            msg: to_json_binary(&astroport_pair::ExecuteMsg::WithdrawLiquidity {
+                amount: shares_to_burn,
+            })?,
+            funds: vec![],
+        });
+
+        // What `WithdrawLiquidity` returns, estimated from the
+        // pre-withdrawal reserve ratio since the pool doesn't report it
+        // back synchronously without a reply round-trip.
+        let withdrawn_paired = paired_reserve.multiply_ratio(shares_to_burn, total_share);
+
+        if withdrawn_paired.is_zero() {
+            return Ok(vec![withdraw_msg]);
+        }
+
+        // Same locally-computed guard as `deposit`'s swap: reject up front
+        // if the pool's reserves are too thin to honor this swap within
+        // `max_slippage`, instead of discovering it only via `max_spread`
+        // at execution time.
+        Self::quote_swap(
+            paired_reserve,
+            usdc_reserve,
+            withdrawn_paired,
+            risk_parameters.max_slippage,
+        )?;
+
+        let swap_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.contract_addr.to_string(),
+            msg: to_json_binary(&astroport_pair::ExecuteMsg::Swap {
+                offer_asset: astroport_pair::Asset {
+                    info: astroport_pair::AssetInfo::NativeToken {
+                        denom: paired_denom.clone(),
+                    },
+                    amount: withdrawn_paired,
+                },
+                max_spread: Some(risk_parameters.max_slippage),
+                belief_price: Some(Decimal::from_ratio(paired_reserve, usdc_reserve)),
+            })?,
+            funds: vec![Coin {
+                denom: paired_denom,
+                amount: withdrawn_paired,
+            }],
+        });
+
+        Ok(vec![withdraw_msg, swap_msg])
+    }
+
+    fn query_balance(&self, deps: Deps, env: Env) -> StdResult<Uint128> {
+        let lp_shares = AMM_LP_SHARES.may_load(deps.storage, &self.name)?.unwrap_or_default();
+        if lp_shares.is_zero() {
+            return Ok(Uint128::zero());
+        }
+
+        let (usdc_reserve, paired_reserve, paired_denom, total_share) = self.query_reserves(deps)?;
+
+        Ok(Self::pool_value(
+            deps,
+            &env,
+            usdc_reserve,
+            paired_reserve,
+            &paired_denom,
+            lp_shares,
+            total_share,
+        )?)
+    }
+
+    fn query_apy(&self, _deps: Deps, _env: Env) -> StdResult<Decimal> {
+        // Swap-fee yield isn't exposed as a queryable rate the way a lending
+        // pool's interest rate is; callers value this adapter via realized
+        // LP growth (`query_balance`) instead of a quoted APY.
+        Ok(Decimal::zero())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn protocol_type(&self) -> &str {
+        "astroport_amm"
+    }
+}
+
+/// Integer square root via Newton's method, used to size the AMM deposit
+/// swap (`sqrt(r_x * (r_x + d)) - r_x`). `Uint256` avoids overflow on the
+/// intermediate product for realistic reserve/deposit sizes.
+fn isqrt(value: Uint256) -> Uint256 {
+    if value.is_zero() {
+        return Uint256::zero();
+    }
+
+    let mut x = value;
+    let mut y = (x + Uint256::one()) / Uint256::from(2u128);
+    while y < x {
+        x = y;
+        y = (x + value / x) / Uint256::from(2u128);
+    }
+    x
+}
+
 // Protocol interfaces - these would be imported from respective crates in production
+// Minimal cw20 interface, just the `Send` variant adapters need to route a
+// deposit into a contract alongside a hook message (production would pull
+// this from the `cw20` crate, same rationale as the other protocol modules
+// below).
+pub mod cw20 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Binary, Uint128};
+
+    #[cw_serde]
+    pub enum Cw20ExecuteMsg {
+        Send {
+            contract: String,
+            amount: Uint128,
+            msg: Binary,
+        },
+        Transfer {
+            recipient: String,
+            amount: Uint128,
+        },
+        // `expires` is simplified to a block height (production would use
+        // `cw_utils::Expiration`); `None` means no expiration.
+        IncreaseAllowance {
+            spender: String,
+            amount: Uint128,
+            expires: Option<u64>,
+        },
+    }
+
+    // The hook payload a cw20 contract's `Send` wraps around its own
+    // `Cw20ExecuteMsg::Send`, delivered to `ExecuteMsg::Receive`.
+    #[cw_serde]
+    pub struct Cw20ReceiveMsg {
+        pub sender: String,
+        pub amount: Uint128,
+        pub msg: Binary,
+    }
+}
+
 pub mod helix {
     use cosmwasm_schema::cw_serde;
     use cosmwasm_std::{Decimal, Uint128};
@@ -253,7 +840,11 @@ pub mod helix {
     #[cw_serde]
     pub enum ExecuteMsg {
         Deposit {},
-        Withdraw { amount: Uint128 },
+        // `min_out` lets the vault revert the whole rebalance batch if
+        // Helix can't honor the caller's slippage floor, instead of
+        // silently returning less than expected.
+        Withdraw { amount: Uint128, min_out: Uint128 },
+        ClaimRewards {},
     }
 
     #[cw_serde]
@@ -280,7 +871,11 @@ pub mod hydro {
     #[cw_serde]
     pub enum ExecuteMsg {
         SupplyLiquidity {},
-        WithdrawLiquidity { amount: Uint128 },
+        // `min_out` lets the vault revert the whole rebalance batch if
+        // Hydro can't honor the caller's slippage floor, instead of
+        // silently returning less than expected.
+        WithdrawLiquidity { amount: Uint128, min_out: Uint128 },
+        ClaimRewards {},
     }
 
     #[cw_serde]
@@ -307,7 +902,11 @@ pub mod neptune {
     #[cw_serde]
     pub enum ExecuteMsg {
         Stake {},
-        Unstake { amount: Uint128 },
+        // `min_out` lets the vault revert the whole rebalance batch if
+        // Neptune can't honor the caller's slippage floor, instead of
+        // silently returning less than expected.
+        Unstake { amount: Uint128, min_out: Uint128 },
+        ClaimRewards {},
     }
 
     #[cw_serde]
@@ -327,24 +926,87 @@ pub mod neptune {
     }
 }
 
+pub mod astroport_pair {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Decimal, Uint128};
+
+    #[cw_serde]
+    pub enum AssetInfo {
+        NativeToken { denom: String },
+    }
+
+    #[cw_serde]
+    pub struct Asset {
+        pub info: AssetInfo,
+        pub amount: Uint128,
+    }
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        ProvideLiquidity {
+            assets: Vec<Asset>,
+            slippage_tolerance: Option<Decimal>,
+        },
+        // AstroBalance tracks its own LP share ledger (`AMM_LP_SHARES`)
+        // rather than holding a separate LP token balance, so this burns
+        // the amount given directly instead of taking a Cw20 send.
+        WithdrawLiquidity {
+            amount: Uint128,
+        },
+        Swap {
+            offer_asset: Asset,
+            max_spread: Option<Decimal>,
+            belief_price: Option<Decimal>,
+        },
+    }
+
+    #[cw_serde]
+    pub enum QueryMsg {
+        Pool {},
+        Simulation { offer_asset: Asset },
+    }
+
+    #[cw_serde]
+    pub struct PoolResponse {
+        pub assets: Vec<Asset>,
+        pub total_share: Uint128,
+    }
+
+    #[cw_serde]
+    pub struct SimulationResponse {
+        pub return_amount: Uint128,
+    }
+}
+
 // Factory function to create protocol adapters
 pub fn create_protocol_adapter(
     protocol_type: &str,
     contract_addr: Addr,
     name: String,
+    deposit_asset: AssetInfo,
 ) -> Result<Box<dyn YieldProtocol>, ContractError> {
     match protocol_type {
         "helix" => Ok(Box::new(HelixAdapter {
             contract_addr,
             name,
+            deposit_asset,
         })),
         "hydro" => Ok(Box::new(HydroAdapter {
             contract_addr,
             name,
+            deposit_asset,
         })),
         "neptune" => Ok(Box::new(NeptuneAdapter {
             contract_addr,
             name,
+            deposit_asset,
+        })),
+        // The AMM adapter discovers both sides of its pair from the pool's
+        // own reserves rather than a single declared deposit asset, so
+        // `deposit_asset` doesn't apply here.
+        "astroport_amm" => Ok(Box::new(AstroportAmmAdapter {
+            contract_addr,
+            name,
         })),
         _ => Err(ContractError::ProtocolNotFound {
             name: protocol_type.to_string(),
@@ -354,5 +1016,5 @@ pub fn create_protocol_adapter(
 
 // Helper to get all supported protocol types
 pub fn get_supported_protocol_types() -> Vec<&'static str> {
-    vec!["helix", "hydro", "neptune"]
+    vec!["helix", "hydro", "neptune", "astroport_amm"]
 }
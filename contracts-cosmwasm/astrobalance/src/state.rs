@@ -1,14 +1,28 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[cw_serde]
 pub struct Config {
     pub admin: Addr,
     pub ai_operator: Addr,
-    pub base_denom: String,           // USDC - our standard denomination
-    pub accepted_denoms: Vec<String>, // List of supported tokens
+    pub base_denom: String, // USDC - our standard denomination
+    // Supported deposit/withdraw assets, native or cw20.
+    pub accepted_denoms: Vec<AssetInfo>,
     pub astroport_router: String,     // Added to match with msg::Config
+    // Seconds a `Withdraw` must sit in the claims queue before `Claim {}`
+    // will release it. `None` keeps the legacy immediate-payout behavior.
+    pub unbonding_period: Option<u64>,
+    // Cut of realized gains taken on `Withdraw`/`Claim`, in basis points.
+    pub performance_fee_bps: u16,
+    // Recipient of the performance fee.
+    pub fee_collector: Addr,
+    // Address proposed by `UpdateAdmin`, cleared once `AcceptAdmin` or
+    // `CancelAdminChange` resolves it. `admin` only changes on acceptance,
+    // so a typo'd proposal can never brick control of the contract.
+    pub pending_admin: Option<Addr>,
+    // Same two-step handover as `pending_admin`, for `ai_operator`.
+    pub pending_ai_operator: Option<Addr>,
 }
 
 #[cw_serde]
@@ -21,8 +35,37 @@ pub struct UserDeposit {
 
 #[cw_serde]
 pub struct UserInfo {
-    pub total_usdc_value: Uint128,
     pub deposits: Vec<UserDeposit>,
+    // High-water mark, in asset (not share) terms: the value above which a
+    // payout is treated as realized gain and subject to the performance
+    // fee. Each withdrawal scales it down by the fraction of value withdrawn,
+    // the same fraction of the gain its fee was charged on, so the gain left
+    // on the remaining position stays taxable and none is charged twice.
+    pub cost_basis: Uint128,
+}
+
+// What a protocol adapter's `deposit` actually hands over: either native
+// coins attached as `funds`, or a cw20 token routed via
+// `Cw20ExecuteMsg::Send`. Lets `create_protocol_adapter` stop assuming every
+// protocol takes the base denom as a plain `Coin`.
+#[cw_serde]
+pub enum AssetInfo {
+    Native(String),
+    Cw20(Addr),
+}
+
+impl AssetInfo {
+    /// Human-readable identifier used anywhere an `AssetInfo` needs to be
+    /// keyed or displayed as a single string - attributes, errors, and the
+    /// `PAIR_REGISTRY`/price-feed maps that are still keyed by plain
+    /// strings: the denom for a native asset, the contract address for a
+    /// cw20 one.
+    pub fn label(&self) -> String {
+        match self {
+            AssetInfo::Native(denom) => denom.clone(),
+            AssetInfo::Cw20(addr) => addr.to_string(),
+        }
+    }
 }
 
 #[cw_serde]
@@ -32,6 +75,7 @@ pub struct ProtocolInfo {
     pub allocation_percentage: Decimal, // Current allocation percentage
     pub current_balance: Uint128,       // Current USDC value in this protocol
     pub enabled: bool,
+    pub deposit_asset: AssetInfo, // Asset this protocol's `deposit` expects
 }
 
 #[cw_serde]
@@ -40,20 +84,215 @@ pub struct RiskParameters {
     pub max_slippage: Decimal,                // Max slippage for swaps
     pub rebalance_threshold: Decimal,         // Min difference to trigger rebalance
     pub emergency_withdrawal_fee: Decimal,    // Fee for emergency withdrawals
-}
-
-#[cw_serde]
-pub struct RebalanceRecord {
-    pub timestamp: Timestamp,
-    pub initiated_by: Addr,
-    pub old_allocations: Vec<(String, Decimal)>,
-    pub new_allocations: Vec<(String, Decimal)>,
-    pub reason: String,
+    // Seconds a Pyth price (spot or EMA) may sit unpublished before a
+    // deposit valuation rejects it as stale.
+    pub max_price_staleness: u64,
+    // Cut of vault-wide realized yield taken on each `Rebalance`, split
+    // across `FEE_RECIPIENTS`. Distinct from `Config.performance_fee_bps`,
+    // which is charged per-user on `Withdraw`/`Claim` instead.
+    pub performance_fee: Decimal,
+    // Max allowed relative divergence between a live Astroport spot quote
+    // and `twap::twap_since_genesis` before `Rebalance` rejects with
+    // `PriceDeviationTooHigh`, guarding against a spot price manipulated
+    // within a single block.
+    pub max_price_deviation: Decimal,
+    // Floor applied to every rebalance leg's `RebalanceAction.min_out`:
+    // `amount * (1 - max_slippage_bps)`. Distinct from `max_slippage`, which
+    // only governs Astroport swap conversions - this guards a protocol
+    // adapter's own deposit/withdraw call against share-price or exchange-
+    // rate drift between `calculate_rebalance_actions` and execution, even
+    // for adapters that never swap. Must be strictly between 0% and 100%.
+    pub max_slippage_bps: Decimal,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const USER_INFOS: Map<&Addr, UserInfo> = Map::new("user_infos");
 pub const PROTOCOLS: Map<&String, ProtocolInfo> = Map::new("protocols");
+
+// Schema version of the data stored under `PROTOCOLS`/`TX_LOG`/etc., bumped
+// by `migrate` whenever one of those layouts changes. Distinct from cw2's
+// `CONTRACT_VERSION` (the crate's semver, tracking code identity), this
+// tracks the shape of what's actually on disk so `migrate` knows whether a
+// translation step is still owed or has already run.
+pub const CONTRACT_STATE_VERSION: Item<u64> = Item::new("contract_state_version");
 pub const RISK_PARAMETERS: Item<RiskParameters> = Item::new("risk_parameters");
-pub const REBALANCE_HISTORY: Item<Vec<RebalanceRecord>> = Item::new("rebalance_history");
+// Nominal total value of the vault: the sum of every credited deposit minus
+// every paid-out withdrawal, refreshed from live protocol balances by
+// `SyncBalances` and by the `sync_balances` option, which resyncs before a
+// withdrawal or rebalance moves anything. Doubles as the ERC-4626 `total_assets` that
+// share issuance/redemption is priced against.
 pub const TOTAL_USDC_VALUE: Item<Uint128> = Item::new("total_usdc_value");
+
+// ERC-4626-style vault shares: a user's claim on `TOTAL_USDC_VALUE` is the
+// fraction `SHARES[addr] / TOTAL_SHARES`, not a nominal deposited amount.
+// This is what lets accrued protocol yield (or loss) flow to whoever holds
+// shares when it's realized, instead of only to whoever deposited it.
+pub const SHARES: Map<&Addr, Uint128> = Map::new("shares");
+pub const TOTAL_SHARES: Item<Uint128> = Item::new("total_shares");
+
+// Oracle configuration: the Pyth contract to query, a 32-byte price-feed id
+// per denom, and how old a price is allowed to be before it's rejected.
+pub const ORACLE_ADDR: Item<Addr> = Item::new("oracle_addr");
+pub const PRICE_FEED_IDS: Map<&str, Binary> = Map::new("price_feed_ids");
+pub const ORACLE_MAX_STALENESS: Item<u64> = Item::new("oracle_max_staleness");
+
+// Hashed SNIP-style viewing keys, one per address that has opted into
+// gating its own `GetUserTxHistory` reads (the per-deposit breakdown;
+// `GetUserInfo` is public aggregate-only and needs no key).
+pub const VIEWING_KEYS: Map<&Addr, String> = Map::new("viewing_keys");
+
+/// Contract-wide killswitch, graded so an operator can halt new inflows
+/// without blocking the user exits that `StopAll` still allows.
+#[cw_serde]
+#[derive(Copy, Eq, Default)]
+pub enum ContractStatus {
+    #[default]
+    Normal,
+    StopDeposits,
+    // Also blocks protocol management (`AddProtocol`/`UpdateProtocol`/
+    // `RemoveProtocol`) and rebalancing on top of `StopDeposits`, but still
+    // allows `EmergencyWithdraw {}` so users can exit.
+    EmergencyOnly,
+    StopAll,
+}
+
+// `CONTRACT_STATUS` records not just the current level but why and when an
+// admin set it, so an operator reading it back later has the incident
+// context rather than a bare enum.
+#[cw_serde]
+pub struct ContractStatusInfo {
+    pub status: ContractStatus,
+    pub reason: String,
+    pub updated_at: Timestamp,
+}
+
+pub const CONTRACT_STATUS: Item<ContractStatusInfo> = Item::new("contract_status");
+
+/// A pending withdrawal sitting out the unbonding window before `Claim {}`
+/// can release it to the user as a `BankMsg::Send`.
+#[cw_serde]
+pub struct Claim {
+    pub amount: Uint128,
+    pub denom: String,
+    pub release_at: Timestamp,
+}
+
+pub const CLAIMS: Map<&Addr, Vec<Claim>> = Map::new("claims");
+
+/// Performance fees accrued and not yet paid out; `ClaimFees` pays the pot
+/// out and resets it to zero.
+pub const ACCRUED_FEES: Item<Uint128> = Item::new("accrued_fees");
+
+/// The vault-wide analog of `UserInfo.cost_basis`: the `TOTAL_USDC_VALUE`
+/// above which a `Rebalance` treats further growth as realized yield subject
+/// to `RiskParameters.performance_fee`. Unset until the first `Rebalance`
+/// runs, which seeds it at the current total instead of taxing it, so
+/// existing principal is never charged.
+pub const VAULT_HIGH_WATER_MARK: Item<Uint128> = Item::new("vault_high_water_mark");
+
+/// Weighted split of the vault-wide performance fee, set by
+/// `SetFeeRecipients`. Weights must sum to `Decimal::one()`.
+pub const FEE_RECIPIENTS: Item<Vec<(Addr, Decimal)>> = Item::new("fee_recipients");
+
+/// Astroport pair contract for a denom pair, set by `RegisterPair`. Keyed by
+/// `token_converter::normalized_pair_key`, so lookups don't care which denom
+/// was named first. Lets `AstroportRouter::convert_to_usdc`/`convert_from_usdc`
+/// swap directly against the pair instead of hopping through the router's
+/// `SimulateSwapOperations`/`ExecuteSwapOperations` when a pair is registered.
+pub const PAIR_REGISTRY: Map<(String, String), Addr> = Map::new("pair_registry");
+
+/// Scratch state bridging a deposit's Astroport swap `SubMsg` to its `reply`
+/// handler: everything needed to credit `UserInfo`/`TOTAL_USDC_VALUE` once
+/// the swap's actual USDC output is known. Only one deposit swap can be
+/// in flight at a time, since submessages replay before `execute` returns.
+#[cw_serde]
+pub struct PendingDeposit {
+    pub depositor: Addr,
+    // Validated account the deposit's shares/value are credited to; equal
+    // to `depositor` unless the deposit named another recipient.
+    pub recipient: Addr,
+    pub original_denom: String,
+    pub original_amount: Uint128,
+    pub timestamp: Timestamp,
+}
+
+pub const PENDING_DEPOSIT: Item<PendingDeposit> = Item::new("pending_deposit");
+
+#[cw_serde]
+pub enum RebalanceLegKind {
+    Withdrawal,
+    Deposit,
+}
+
+/// One leg of an in-flight `Rebalance` whose realized amount isn't known
+/// until its terminal submessage replies.
+#[cw_serde]
+pub enum PendingRebalanceLeg {
+    /// The leg's final message is a direct protocol-adapter call that
+    /// doesn't echo back how much it actually moved; `planned_amount` is
+    /// credited as realized once the message succeeds.
+    Direct {
+        protocol_name: String,
+        kind: RebalanceLegKind,
+        planned_amount: Uint128,
+    },
+    /// The leg's final message is an Astroport swap; its `return_amount`
+    /// event is the realized amount for this leg.
+    Swapped {
+        protocol_name: String,
+        kind: RebalanceLegKind,
+    },
+    /// A deposit leg whose funding swap (base denom into `protocol_name`'s
+    /// deposit asset) just replied: the actual deposit call still needs to
+    /// be dispatched, floored at `min_out`, against the swap's real output
+    /// instead of the pre-execution simulation `calculate_rebalance_actions`
+    /// used to plan it.
+    AwaitingFundedDeposit {
+        protocol_name: String,
+        min_out: Uint128,
+    },
+}
+
+/// Scratch state bridging a `Rebalance`'s withdraw/deposit `SubMsg`s to
+/// `reply`: the allocations and reason to commit once every leg has
+/// confirmed its realized amount, so a partial fill or failure never leaves
+/// `PROTOCOLS`/allocation percentages diverged from what actually executed.
+/// `pending_legs` is consumed FIFO, in the same order its submessages were
+/// dispatched. Only one `Rebalance` can be in flight at a time, the same
+/// constraint `PENDING_DEPOSIT` places on deposit swaps.
+#[cw_serde]
+pub struct RebalanceInProgress {
+    pub sender: Addr,
+    pub target_allocations: Vec<(String, Decimal)>,
+    pub reason: String,
+    pub pending_legs: Vec<PendingRebalanceLeg>,
+    pub realized: Vec<(String, RebalanceLegKind, Uint128)>,
+}
+
+pub const REBALANCE_IN_PROGRESS: Item<RebalanceInProgress> = Item::new("rebalance_in_progress");
+
+/// Scratch state bridging `HarvestRewards`'s claim `SubMsg`s to `reply`,
+/// mirroring `RebalanceInProgress` for reward claims whose real payout
+/// isn't known until each protocol's claim call replies. `pending_protocols`
+/// is consumed FIFO, in the same order claim submessages were dispatched.
+/// Only one harvest can be in flight at a time, the same constraint
+/// `PENDING_DEPOSIT` places on deposit swaps.
+#[cw_serde]
+pub struct HarvestInProgress {
+    pub sender: Addr,
+    // If true, `reply` redeposits the harvested total back into protocols
+    // per their current `allocation_percentage` once every claim has
+    // settled; if false, the harvested USDC is simply left in the vault's
+    // balance as realized value.
+    pub compound: bool,
+    pub pending_protocols: Vec<String>,
+    pub realized: Vec<(String, Uint128)>,
+}
+
+pub const HARVEST_IN_PROGRESS: Item<HarvestInProgress> = Item::new("harvest_in_progress");
+
+// LP shares an `AstroportAmmAdapter` protocol holds in its pool, keyed by
+// protocol name. Adapter-local rather than a `ProtocolInfo` field since it's
+// meaningful only for constant-product AMM protocols; every other adapter's
+// `current_balance` is already a complete USDC-denominated position.
+pub const AMM_LP_SHARES: Map<&String, Uint128> = Map::new("amm_lp_shares");
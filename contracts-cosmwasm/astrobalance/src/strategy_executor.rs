@@ -1,12 +1,48 @@
 use cosmwasm_std::{
-    Addr, Decimal, Deps, DepsMut, Env, Fraction, MessageInfo, Response, StdError, StdResult,
-    Storage, Uint128,
+    Addr, CosmosMsg, Decimal, Deps, DepsMut, Env, Fraction, MessageInfo, Response, StdError,
+    StdResult, SubMsg, Uint128,
 };
 use std::collections::HashMap;
 
 use crate::error::ContractError;
+use crate::limiters;
 use crate::protocols::create_protocol_adapter;
-use crate::state::{ProtocolInfo, RebalanceRecord, PROTOCOLS, REBALANCE_HISTORY, TOTAL_USDC_VALUE};
+use crate::state::{
+    AssetInfo, HarvestInProgress, PendingRebalanceLeg, ProtocolInfo, RebalanceInProgress,
+    RebalanceLegKind, CONFIG, HARVEST_IN_PROGRESS, PROTOCOLS, REBALANCE_IN_PROGRESS,
+    TOTAL_USDC_VALUE,
+};
+use crate::swap_math;
+use crate::token_converter::AstroportRouter;
+use crate::tx_log::{self, TxKind};
+
+// Reply id for the terminal submessage of every `Rebalance` withdraw/deposit
+// leg; `reply` accumulates each leg's realized amount into
+// `RebalanceInProgress` and only commits `PROTOCOLS`/allocation percentages
+// once every leg dispatched by `StrategyExecutor::execute_rebalance` has
+// replied.
+pub const REBALANCE_LEG_REPLY_ID: u64 = 2;
+
+// Reply id for each protocol's reward-claim submessage dispatched by
+// `StrategyExecutor::harvest_rewards`; `reply` accumulates the realized
+// claimed amount into `HarvestInProgress` and, once every protocol's claim
+// has replied, finalizes the harvest (optionally redepositing it).
+pub const HARVEST_REPLY_ID: u64 = 3;
+
+/// Pushes every message of a leg onto `messages`, wrapping only the last one
+/// (the adapter call whose success actually confirms the leg) in
+/// `reply_on_success(.., reply_id)`; any earlier message (e.g. a
+/// `Cw20::IncreaseAllowance`) is dispatched plain.
+fn push_leg_messages(messages: &mut Vec<SubMsg>, leg_msgs: Vec<CosmosMsg>, reply_id: u64) {
+    let last_idx = leg_msgs.len() - 1;
+    for (i, msg) in leg_msgs.into_iter().enumerate() {
+        if i == last_idx {
+            messages.push(SubMsg::reply_on_success(msg, reply_id));
+        } else {
+            messages.push(SubMsg::new(msg));
+        }
+    }
+}
 
 pub struct StrategyExecutor {}
 
@@ -33,12 +69,22 @@ impl StrategyExecutor {
         Ok(())
     }
 
-    // Calculate actions needed to achieve target allocations
+    // Calculate actions needed to achieve target allocations. When
+    // `use_live_allocation` is set, each protocol's current percentage is
+    // derived from `current_balance / total_value` instead of the stored
+    // `allocation_percentage`, so moves are sized pro-rata against what's
+    // genuinely held right now rather than bookkeeping that may have
+    // drifted since the last rebalance or sync. The caller is responsible
+    // for refreshing `current_balance`/`total_value` beforehand (see
+    // `execute_rebalance`'s `sync_balances`) - this just changes which
+    // field the percentage is read from.
     pub fn calculate_rebalance_actions(
         deps: Deps,
         current_protocols: Vec<ProtocolInfo>,
         target_allocations: &[(String, Decimal)],
         total_value: Uint128,
+        max_slippage_bps: Decimal,
+        use_live_allocation: bool,
     ) -> StdResult<RebalanceActions> {
         let mut withdrawals = vec![];
         let mut deposits = vec![];
@@ -46,9 +92,18 @@ impl StrategyExecutor {
         // Create maps for easier lookup
         let mut current_map: HashMap<String, (Decimal, Uint128)> = HashMap::new();
         for protocol in current_protocols {
+            let current_percentage = if use_live_allocation {
+                if total_value.is_zero() {
+                    Decimal::zero()
+                } else {
+                    Decimal::from_ratio(protocol.current_balance, total_value)
+                }
+            } else {
+                protocol.allocation_percentage
+            };
             current_map.insert(
                 protocol.name.clone(),
-                (protocol.allocation_percentage, protocol.current_balance),
+                (current_percentage, protocol.current_balance),
             );
         }
 
@@ -77,12 +132,30 @@ impl StrategyExecutor {
                             protocol_name: name.clone(),
                             contract_addr: protocol_info.contract_addr,
                             amount: withdrawal_amount,
+                            min_out: swap_math::min_receive(withdrawal_amount, max_slippage_bps),
+                            deposit_asset: protocol_info.deposit_asset,
                         });
                     }
                 }
             }
         }
 
+        // A live-percentage rebalance sizes withdrawals against balances
+        // queried moments ago; if reality still moved between that query
+        // and here (e.g. a concurrent withdrawal), the planned withdrawals
+        // could in principle ask for more than `total_value` actually
+        // holds. Scale every withdrawal down proportionally so their sum
+        // never exceeds the realized total rather than over-withdrawing.
+        let total_withdrawals: Uint128 = withdrawals.iter().map(|a| a.amount).sum();
+        if use_live_allocation && total_withdrawals > total_value && !total_withdrawals.is_zero() {
+            for action in &mut withdrawals {
+                action.amount = action
+                    .amount
+                    .multiply_ratio(total_value, total_withdrawals);
+                action.min_out = swap_math::min_receive(action.amount, max_slippage_bps);
+            }
+        }
+
         // Calculate deposits (protocols that need increase)
         for (name, target_percentage) in &target_map {
             let zero_tuple = (Decimal::zero(), Uint128::zero());
@@ -108,6 +181,8 @@ impl StrategyExecutor {
                             protocol_name: name.clone(),
                             contract_addr: protocol_info.contract_addr,
                             amount: deposit_amount,
+                            min_out: swap_math::min_receive(deposit_amount, max_slippage_bps),
+                            deposit_asset: protocol_info.deposit_asset,
                         });
                     }
                 }
@@ -128,10 +203,28 @@ impl StrategyExecutor {
         target_allocations: Vec<(String, Decimal)>,
         reason: String,
         max_allocation_per_protocol: Decimal,
+        max_slippage: Decimal,
+        max_slippage_bps: Decimal,
+        use_live_allocation: bool,
     ) -> Result<Response, ContractError> {
         // Validate allocations first
         Self::validate_allocations(&target_allocations, max_allocation_per_protocol)?;
 
+        // Submessages from an earlier `Rebalance` are still replying; only
+        // one can be in flight at a time; see `RebalanceInProgress`.
+        if REBALANCE_IN_PROGRESS.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::RebalanceAlreadyInProgress {});
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+        let router = AstroportRouter(deps.api.addr_validate(&config.astroport_router)?);
+
+        // Reject any single protocol jump that a registered limiter forbids,
+        // whether an absolute cap or too large a move from its recent average.
+        for (protocol, allocation) in &target_allocations {
+            limiters::check_limiters(deps.storage, protocol, *allocation)?;
+        }
+
         // Load current protocol data
         let mut current_protocols = vec![];
         let protocol_names: Vec<String> = PROTOCOLS
@@ -145,12 +238,6 @@ impl StrategyExecutor {
             }
         }
 
-        // Save old allocations for history
-        let old_allocations: Vec<(String, Decimal)> = current_protocols
-            .iter()
-            .map(|p| (p.name.clone(), p.allocation_percentage))
-            .collect();
-
         // Get total value
         let total_value = TOTAL_USDC_VALUE.load(deps.storage)?;
 
@@ -160,67 +247,222 @@ impl StrategyExecutor {
             current_protocols,
             &target_allocations,
             total_value,
+            max_slippage_bps,
+            use_live_allocation,
         )?;
 
-        // Start building messages and response
-        let mut messages = vec![];
+        // Start building submessages and the legs `reply` will reconcile
+        // once their terminal message actually executes.
+        let mut messages: Vec<SubMsg> = vec![];
+        let mut pending_legs: Vec<PendingRebalanceLeg> = vec![];
 
-        // First execute all withdrawals
+        // First execute all withdrawals. A withdrawal that lands in a denom
+        // other than the vault's base denom is swapped back into it through
+        // the router immediately, so every subsequent deposit leg below can
+        // assume it's spending `config.base_denom`.
         for action in &actions.withdrawals {
             let protocol_adapter = create_protocol_adapter(
                 &action.protocol_name,
                 action.contract_addr.clone(),
                 action.protocol_name.clone(),
+                action.deposit_asset.clone(),
+            )?;
+
+            let withdraw_msgs = protocol_adapter.withdraw(
+                deps.branch(),
+                env.clone(),
+                action.amount,
+                action.min_out,
             )?;
 
-            let withdraw_msgs =
-                protocol_adapter.withdraw(deps.branch(), env.clone(), action.amount)?;
-            messages.extend(withdraw_msgs);
+            // The AMM adapter already returns USDC-denominated value from its
+            // own internal swap, so `deposit_asset` doesn't describe what it
+            // hands back and needs no further conversion here.
+            let needs_conversion = protocol_adapter.protocol_type() != "astroport_amm"
+                && matches!(&action.deposit_asset, AssetInfo::Native(denom) if denom != &config.base_denom);
+
+            if !needs_conversion {
+                // The adapter call itself is this leg's terminal message; it
+                // doesn't echo back a realized amount, so the planned
+                // `action.amount` is credited once it succeeds.
+                push_leg_messages(&mut messages, withdraw_msgs, REBALANCE_LEG_REPLY_ID);
+                pending_legs.push(PendingRebalanceLeg::Direct {
+                    protocol_name: action.protocol_name.clone(),
+                    kind: RebalanceLegKind::Withdrawal,
+                    planned_amount: action.amount,
+                });
+                continue;
+            }
+
+            messages.extend(withdraw_msgs.into_iter().map(SubMsg::new));
+
+            if let AssetInfo::Native(denom) = &action.deposit_asset {
+                let (swap_msg, _converted) = router.convert_denom(
+                    deps.as_ref(),
+                    denom,
+                    &config.base_denom,
+                    action.amount,
+                    max_slippage,
+                )?;
+                messages.push(SubMsg::reply_on_success(swap_msg, REBALANCE_LEG_REPLY_ID));
+            }
+            pending_legs.push(PendingRebalanceLeg::Swapped {
+                protocol_name: action.protocol_name.clone(),
+                kind: RebalanceLegKind::Withdrawal,
+            });
         }
 
-        // Then execute all deposits
+        // Then execute all deposits. A deposit whose protocol expects a
+        // denom other than the base denom is funded by first swapping the
+        // base-denom amount into that denom through the router; the actual
+        // deposit call is deferred to `reply` once that swap's real output
+        // is known, instead of trusting the pre-execution simulation.
         for action in &actions.deposits {
             let protocol_adapter = create_protocol_adapter(
                 &action.protocol_name,
                 action.contract_addr.clone(),
                 action.protocol_name.clone(),
+                action.deposit_asset.clone(),
             )?;
 
-            let deposit_msgs =
-                protocol_adapter.deposit(deps.branch(), env.clone(), action.amount)?;
-            messages.extend(deposit_msgs);
+            // The AMM adapter takes a USDC-denominated amount and performs
+            // its own internal swap against its pool, so it's funded
+            // directly rather than pre-converted here.
+            let needs_conversion = protocol_adapter.protocol_type() != "astroport_amm"
+                && matches!(&action.deposit_asset, AssetInfo::Native(denom) if denom != &config.base_denom);
+
+            if needs_conversion {
+                if let AssetInfo::Native(denom) = &action.deposit_asset {
+                    let (swap_msg, _converted) = router.convert_denom(
+                        deps.as_ref(),
+                        &config.base_denom,
+                        denom,
+                        action.amount,
+                        max_slippage,
+                    )?;
+                    messages.push(SubMsg::reply_on_success(swap_msg, REBALANCE_LEG_REPLY_ID));
+                }
+                pending_legs.push(PendingRebalanceLeg::AwaitingFundedDeposit {
+                    protocol_name: action.protocol_name.clone(),
+                    min_out: action.min_out,
+                });
+                continue;
+            }
+
+            // Either the AMM adapter (USDC-denominated, no pre-conversion)
+            // or a protocol whose deposit asset is already the base denom
+            // or a cw20 - none of these need funding ahead of the deposit
+            // call, so it can be dispatched directly.
+            let deposit_msgs = protocol_adapter.deposit(
+                deps.branch(),
+                env.clone(),
+                action.amount,
+                action.min_out,
+            )?;
+            push_leg_messages(&mut messages, deposit_msgs, REBALANCE_LEG_REPLY_ID);
+            pending_legs.push(PendingRebalanceLeg::Direct {
+                protocol_name: action.protocol_name.clone(),
+                kind: RebalanceLegKind::Deposit,
+                planned_amount: action.amount,
+            });
+        }
+
+        if pending_legs.is_empty() {
+            // Nothing to reconcile - every protocol was already at its
+            // target allocation - so there's no reply to wait on.
+            return Self::finalize_rebalance(
+                deps,
+                env,
+                RebalanceInProgress {
+                    sender: info.sender,
+                    target_allocations,
+                    reason,
+                    pending_legs,
+                    realized: vec![],
+                },
+            );
+        }
+
+        REBALANCE_IN_PROGRESS.save(
+            deps.storage,
+            &RebalanceInProgress {
+                sender: info.sender,
+                target_allocations,
+                reason,
+                pending_legs,
+                realized: vec![],
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_submessages(messages)
+            .add_attribute("method", "rebalance")
+            .add_attribute("status", "pending")
+            .add_attribute("withdrawals", actions.withdrawals.len().to_string())
+            .add_attribute("deposits", actions.deposits.len().to_string()))
+    }
+
+    /// Commits a `Rebalance`'s final `PROTOCOLS` balances and allocation
+    /// percentages once every leg in `in_progress.realized` has reported its
+    /// actual realized amount, recording the rebalance in `tx_log` with the
+    /// real total moved rather than what was planned.
+    pub fn finalize_rebalance(
+        deps: DepsMut,
+        env: Env,
+        in_progress: RebalanceInProgress,
+    ) -> Result<Response, ContractError> {
+        let mut withdrawn: HashMap<String, Uint128> = HashMap::new();
+        let mut deposited: HashMap<String, Uint128> = HashMap::new();
+        let mut total_moved = Uint128::zero();
+
+        for (protocol_name, kind, amount) in &in_progress.realized {
+            total_moved += *amount;
+            let bucket = match kind {
+                RebalanceLegKind::Withdrawal => &mut withdrawn,
+                RebalanceLegKind::Deposit => &mut deposited,
+            };
+            *bucket.entry(protocol_name.clone()).or_insert(Uint128::zero()) += *amount;
         }
 
-        // Update protocol allocations and balances
-        for (name, new_allocation) in &target_allocations {
+        for (name, new_allocation) in &in_progress.target_allocations {
             PROTOCOLS.update(deps.storage, name, |protocol_opt| -> StdResult<_> {
                 let mut protocol = protocol_opt.ok_or_else(|| {
                     StdError::generic_err(format!("Protocol not found: {}", name))
                 })?;
 
                 protocol.allocation_percentage = *new_allocation;
-                // The actual balance will be updated in the next query cycle
+                if let Some(amount) = withdrawn.get(name) {
+                    protocol.current_balance = protocol.current_balance.saturating_sub(*amount);
+                }
+                if let Some(amount) = deposited.get(name) {
+                    protocol.current_balance += *amount;
+                }
 
                 Ok(protocol)
             })?;
+
+            limiters::sample_allocation(
+                deps.storage,
+                name,
+                *new_allocation,
+                env.block.time.seconds(),
+            )?;
         }
 
-        // Record rebalance history
-        record_rebalance(
+        tx_log::append_tx(
             deps.storage,
+            env.block.height,
             env.block.time,
-            info.sender,
-            old_allocations,
-            target_allocations.clone(),
-            reason.clone(),
+            in_progress.sender,
+            TxKind::Rebalance,
+            total_moved,
+            in_progress.reason.clone(),
         )?;
 
         Ok(Response::new()
-            .add_messages(messages)
-            .add_attribute("method", "rebalance")
-            .add_attribute("reason", reason)
-            .add_attribute("withdrawals", actions.withdrawals.len().to_string())
-            .add_attribute("deposits", actions.deposits.len().to_string()))
+            .add_attribute("method", "rebalance_finalized")
+            .add_attribute("reason", in_progress.reason)
+            .add_attribute("total_moved", total_moved))
     }
 
     // Check if rebalance is needed based on the threshold
@@ -273,8 +515,12 @@ impl StrategyExecutor {
 
         for name in &protocol_names {
             let protocol_info = PROTOCOLS.load(deps.storage, name)?;
-            let protocol_adapter =
-                create_protocol_adapter(&name, protocol_info.contract_addr.clone(), name.clone())?;
+            let protocol_adapter = create_protocol_adapter(
+                &name,
+                protocol_info.contract_addr.clone(),
+                name.clone(),
+                protocol_info.deposit_asset.clone(),
+            )?;
 
             let current_balance = protocol_adapter.query_balance(deps.as_ref(), env.clone())?;
             balances.insert(name.clone(), current_balance);
@@ -300,6 +546,178 @@ impl StrategyExecutor {
 
         Ok(())
     }
+
+    /// Claims every enabled protocol's pending rewards via its adapter's
+    /// `claim_rewards`, decoupled from deposit/withdraw/rebalance so rewards
+    /// are only realized when this is explicitly called. Each protocol's
+    /// claim is the terminal message of its own leg, reply-wrapped the same
+    /// way a `Rebalance` leg is, since the real payout isn't known until the
+    /// claim succeeds.
+    pub fn harvest_rewards(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        compound: bool,
+    ) -> Result<Response, ContractError> {
+        // Submessages from an earlier harvest are still replying; only one
+        // can be in flight at a time; see `HarvestInProgress`.
+        if HARVEST_IN_PROGRESS.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::HarvestAlreadyInProgress {});
+        }
+
+        let protocol_names: Vec<String> = PROTOCOLS
+            .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .map(|key| key.unwrap())
+            .collect();
+
+        let mut messages: Vec<SubMsg> = vec![];
+        let mut pending_protocols: Vec<String> = vec![];
+
+        for name in protocol_names {
+            let protocol = PROTOCOLS.load(deps.storage, &name)?;
+            if !protocol.enabled {
+                continue;
+            }
+
+            let protocol_adapter = create_protocol_adapter(
+                &name,
+                protocol.contract_addr.clone(),
+                name.clone(),
+                protocol.deposit_asset.clone(),
+            )?;
+
+            let claim_msgs = protocol_adapter.claim_rewards(deps.branch(), env.clone())?;
+            if claim_msgs.is_empty() {
+                // Nothing pending for this protocol (e.g. `astroport_amm`,
+                // whose fees already compound into its own reserves).
+                continue;
+            }
+
+            push_leg_messages(&mut messages, claim_msgs, HARVEST_REPLY_ID);
+            pending_protocols.push(name);
+        }
+
+        if pending_protocols.is_empty() {
+            // No protocol had anything to claim, so there's no reply to
+            // wait on.
+            return Self::finalize_harvest(
+                deps,
+                env,
+                HarvestInProgress {
+                    sender: info.sender,
+                    compound,
+                    pending_protocols,
+                    realized: vec![],
+                },
+            );
+        }
+
+        HARVEST_IN_PROGRESS.save(
+            deps.storage,
+            &HarvestInProgress {
+                sender: info.sender,
+                compound,
+                pending_protocols,
+                realized: vec![],
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_submessages(messages)
+            .add_attribute("method", "harvest_rewards")
+            .add_attribute("status", "pending"))
+    }
+
+    /// Commits a harvest's realized total once every protocol in
+    /// `in_progress.realized` has reported its actual claimed amount. If
+    /// `in_progress.compound` is set, redeposits the harvested total back
+    /// into protocols pro-rata against their current allocation percentage
+    /// instead of leaving it idle in the vault's balance, then records the
+    /// harvest in `tx_log` alongside the rebalance history.
+    pub fn finalize_harvest(
+        mut deps: DepsMut,
+        env: Env,
+        in_progress: HarvestInProgress,
+    ) -> Result<Response, ContractError> {
+        let total_harvested: Uint128 =
+            in_progress.realized.iter().map(|(_, amount)| *amount).sum();
+
+        let mut response = Response::new()
+            .add_attribute("method", "harvest_finalized")
+            .add_attribute("total_harvested", total_harvested);
+
+        if in_progress.compound && !total_harvested.is_zero() {
+            let protocol_names: Vec<String> = PROTOCOLS
+                .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                .map(|key| key.unwrap())
+                .collect();
+
+            for name in protocol_names {
+                let protocol = PROTOCOLS.load(deps.storage, &name)?;
+                if !protocol.enabled || protocol.allocation_percentage.is_zero() {
+                    continue;
+                }
+
+                let deposit_amount = total_harvested.multiply_ratio(
+                    protocol.allocation_percentage.numerator(),
+                    protocol.allocation_percentage.denominator(),
+                );
+                if deposit_amount.is_zero() {
+                    continue;
+                }
+
+                let protocol_adapter = create_protocol_adapter(
+                    &name,
+                    protocol.contract_addr.clone(),
+                    name.clone(),
+                    protocol.deposit_asset.clone(),
+                )?;
+                let deposit_msgs = protocol_adapter.deposit(
+                    deps.branch(),
+                    env.clone(),
+                    deposit_amount,
+                    Uint128::zero(),
+                )?;
+                response = response.add_messages(deposit_msgs);
+
+                PROTOCOLS.update(deps.storage, &name, |maybe_protocol| -> StdResult<_> {
+                    let mut protocol = maybe_protocol.ok_or_else(|| {
+                        StdError::generic_err(format!("Protocol not found: {}", name))
+                    })?;
+                    protocol.current_balance += deposit_amount;
+                    Ok(protocol)
+                })?;
+            }
+        }
+
+        // Every claim already landed in the vault's own bank balance
+        // regardless of `compound`; whether it's then redeposited into
+        // protocols or simply left as idle cash, it's real backing now and
+        // must count toward the share price either way.
+        if !total_harvested.is_zero() {
+            TOTAL_USDC_VALUE.update(deps.storage, |total| -> StdResult<_> {
+                Ok(total + total_harvested)
+            })?;
+        }
+
+        HARVEST_IN_PROGRESS.remove(deps.storage);
+
+        tx_log::append_tx(
+            deps.storage,
+            env.block.height,
+            env.block.time,
+            in_progress.sender,
+            TxKind::Harvest,
+            total_harvested,
+            if in_progress.compound {
+                "harvest+compound".to_string()
+            } else {
+                "harvest".to_string()
+            },
+        )?;
+
+        Ok(response)
+    }
 }
 
 // Structure to track rebalance actions
@@ -307,6 +725,11 @@ pub struct RebalanceAction {
     pub protocol_name: String,
     pub contract_addr: Addr,
     pub amount: Uint128,
+    // Floor the adapter call must honor, `amount * (1 - max_slippage_bps)`,
+    // guarding against share-price or exchange-rate drift between this
+    // calculation and execution.
+    pub min_out: Uint128,
+    pub deposit_asset: AssetInfo,
 }
 
 pub struct RebalanceActions {
@@ -314,30 +737,3 @@ pub struct RebalanceActions {
     pub deposits: Vec<RebalanceAction>,
 }
 
-// Helper to record rebalance history
-pub fn record_rebalance(
-    storage: &mut dyn Storage,
-    timestamp: cosmwasm_std::Timestamp,
-    initiated_by: Addr,
-    old_allocations: Vec<(String, Decimal)>,
-    new_allocations: Vec<(String, Decimal)>,
-    reason: String,
-) -> StdResult<Vec<RebalanceRecord>> {
-    REBALANCE_HISTORY.update(storage, |mut history| -> StdResult<_> {
-        history.push(RebalanceRecord {
-            timestamp,
-            initiated_by,
-            old_allocations,
-            new_allocations,
-            reason,
-        });
-
-        // Limit history size to prevent excessive storage growth
-        if history.len() > 20 {
-            let len = history.len();
-            history = history.drain(0..(len - 20)).collect();
-        }
-
-        Ok(history)
-    })
-}
@@ -0,0 +1,58 @@
+use cosmwasm_std::{Decimal, Uint128};
+
+/// Fee an Astroport constant-product pool takes on every swap, in basis
+/// points (Astroport's standard default pool fee).
+pub const POOL_FEE_BPS: u128 = 30;
+
+/// Constant-product (`x*y=k`) expected output for swapping `amount_in` into
+/// a pool holding `(reserve_in, reserve_out)`, after deducting
+/// `POOL_FEE_BPS`: `amount_out = reserve_out * amount_in_after_fee /
+/// (reserve_in + amount_in_after_fee)`. Computed from reserves this contract
+/// already queried, rather than trusting the pool's own simulation query, so
+/// the guard holds even against a pool that misreports it.
+pub fn expected_output(reserve_in: Uint128, reserve_out: Uint128, amount_in: Uint128) -> Uint128 {
+    if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
+        return Uint128::zero();
+    }
+
+    let amount_in_after_fee = amount_in.multiply_ratio(10_000u128 - POOL_FEE_BPS, 10_000u128);
+    reserve_out.multiply_ratio(amount_in_after_fee, reserve_in + amount_in_after_fee)
+}
+
+/// The least `amount_out` a swap valued at `expected_out` should be allowed
+/// to settle for, per `max_slippage`.
+pub fn min_receive(expected_out: Uint128, max_slippage: Decimal) -> Uint128 {
+    let slippage_floor = Decimal::one() - max_slippage;
+    expected_out.multiply_ratio(slippage_floor.numerator(), slippage_floor.denominator())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_output_matches_constant_product_formula() {
+        // 10_000 : 10_000 pool, swap in 100: after the 0.3% fee that's 99,
+        // and amount_out = 10_000 * 99 / (10_000 + 99) = 98.02... -> 98.
+        let out = expected_output(Uint128::new(10_000), Uint128::new(10_000), Uint128::new(100));
+        assert_eq!(out, Uint128::new(98));
+    }
+
+    #[test]
+    fn expected_output_is_zero_for_empty_reserves() {
+        assert_eq!(
+            expected_output(Uint128::zero(), Uint128::new(10_000), Uint128::new(100)),
+            Uint128::zero()
+        );
+        assert_eq!(
+            expected_output(Uint128::new(10_000), Uint128::zero(), Uint128::new(100)),
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn min_receive_applies_slippage_floor() {
+        let out = min_receive(Uint128::new(1_000), Decimal::percent(2));
+        assert_eq!(out, Uint128::new(980));
+    }
+}
@@ -1,11 +1,13 @@
 use cosmwasm_std::testing::{message_info, mock_env, MockApi, MockQuerier, MockStorage};
 use cosmwasm_std::{
-    to_json_binary, Addr, ContractResult, Decimal, DepsMut, Empty, OwnedDeps, SystemError,
-    SystemResult, Uint128,
+    to_json_binary, Addr, Binary, ContractResult, Decimal, DepsMut, Empty, Event, OwnedDeps,
+    Reply, Response, SubMsgResponse, SubMsgResult, SystemError, SystemResult, Uint128,
 };
 
-use crate::contract::{execute, instantiate};
+use crate::contract::{execute, instantiate, reply};
 use crate::msg::{ExecuteMsg, InstantiateMsg, RiskParametersMsg};
+use crate::state::AssetInfo;
+use crate::strategy_executor::{HARVEST_REPLY_ID, REBALANCE_LEG_REPLY_ID};
 use crate::token_converter::SimulateSwapResponse;
 
 // Use our test models since we're using Option 2
@@ -75,6 +77,10 @@ pub fn router_address() -> String {
     addr("router")
 }
 
+pub fn fee_collector_address() -> String {
+    addr("fee_collector")
+}
+
 // Helper function to setup contract with valid addresses
 pub fn setup_contract(deps: DepsMut) {
     let msg = InstantiateMsg {
@@ -82,8 +88,8 @@ pub fn setup_contract(deps: DepsMut) {
         ai_operator: operator_address(),
         base_denom: "peggy0x87aB3B4C8661e07D6372361211B96ed4Dc36B1B5".to_string(), // USDT
         accepted_denoms: vec![
-            "peggy0x87aB3B4C8661e07D6372361211B96ed4Dc36B1B5".to_string(),
-            "inj".to_string(),
+            AssetInfo::Native("peggy0x87aB3B4C8661e07D6372361211B96ed4Dc36B1B5".to_string()),
+            AssetInfo::Native("inj".to_string()),
         ],
         astroport_router: router_address(),
         risk_parameters: RiskParametersMsg {
@@ -91,7 +97,14 @@ pub fn setup_contract(deps: DepsMut) {
             max_slippage: Decimal::percent(1),
             rebalance_threshold: Decimal::percent(5),
             emergency_withdrawal_fee: Decimal::percent(1),
+            max_price_staleness: 60,
+            performance_fee: Decimal::percent(10),
+            max_price_deviation: Decimal::percent(5),
+            max_slippage_bps: Decimal::percent(1),
         },
+        unbonding_period: None,
+        performance_fee_bps: 1000,
+        fee_collector: fee_collector_address(),
     };
 
     // Fix here: Addr::unchecked instead of using the string directly
@@ -215,6 +228,56 @@ pub fn mock_protocol_response(deps: &mut OwnedDeps<MockStorage, MockApi, MockQue
     });
 }
 
+// Every withdraw/deposit leg of a `Rebalance` now replies before its
+// `PROTOCOLS` balance/allocation is committed (see `RebalanceInProgress`);
+// drives that reply chain to completion so a test can assert on the final
+// state instead of the still-pending response `execute` returns. Only valid
+// when every leg is a direct adapter call with no realized-amount event to
+// parse, which holds for every test protocol (all share the base denom).
+pub fn settle_rebalance(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
+    res: &Response,
+) {
+    for _ in 0..res.messages.len() {
+        let reply_msg = Reply {
+            id: REBALANCE_LEG_REPLY_ID,
+            payload: Binary::default(),
+            gas_used: 0,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+                msg_responses: vec![],
+            }),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    }
+}
+
+// Drives every `Reply` a `HarvestRewards` call's claim submessages owe, in
+// dispatch order, crediting `claimed_amounts[i]` to the i-th claim via the
+// `claimed_amount` wasm event convention `parse_claim_return_amount` reads.
+pub fn settle_harvest(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
+    res: &Response,
+    claimed_amounts: &[u128],
+) {
+    assert_eq!(res.messages.len(), claimed_amounts.len());
+    for amount in claimed_amounts {
+        let reply_msg = Reply {
+            id: HARVEST_REPLY_ID,
+            payload: Binary::default(),
+            gas_used: 0,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("wasm")
+                    .add_attribute("claimed_amount", amount.to_string())],
+                data: None,
+                msg_responses: vec![],
+            }),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    }
+}
+
 // Helper to set up contract with protocols
 pub fn setup_contract_with_protocols(
     deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
@@ -227,6 +290,7 @@ pub fn setup_contract_with_protocols(
         name: "helix".to_string(),
         contract_addr: format!("{}helix", addr("contract_")),
         initial_allocation: Decimal::percent(30),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(
         deps.as_mut(),
@@ -242,6 +306,7 @@ pub fn setup_contract_with_protocols(
         name: "hydro".to_string(),
         contract_addr: format!("{}hydro", addr("contract_")),
         initial_allocation: Decimal::percent(30),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(
         deps.as_mut(),
@@ -257,6 +322,7 @@ pub fn setup_contract_with_protocols(
         name: "neptune".to_string(),
         contract_addr: format!("{}neptune", addr("contract_")),
         initial_allocation: Decimal::percent(40),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(
         deps.as_mut(),
@@ -1,10 +1,82 @@
-use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
-use cosmwasm_std::{coins, from_json, Addr, Uint128};
+use cosmwasm_std::testing::{
+    message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, Addr, Binary, ContractResult, Empty, Event, OwnedDeps,
+    Reply, SubMsgResponse, SubMsgResult, SystemError, SystemResult, Uint128, WasmQuery,
+};
 
-use crate::contract::{execute, query};
-use crate::msg::{ExecuteMsg, GetUserInfoResponse, QueryMsg};
+use crate::contract::{execute, query, reply};
+use crate::msg::{ExecuteMsg, GetDepositQuoteResponse, GetUserInfoResponse, QueryMsg};
+use crate::oracle::pyth;
 use crate::tests::common::*;
 
+/// Registers an oracle for "inj" that quotes a 10.0 spot price and a 9.0 EMA
+/// price, both published at `now`, so the conservative (lower) valuation is
+/// the EMA one.
+fn setup_inj_oracle(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
+    now: u64,
+) {
+    let admin_addr = Addr::unchecked(admin_address());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin_addr, &[]),
+        ExecuteMsg::SetOracleConfig {
+            oracle_addr: "oracle_contract".to_string(),
+            max_staleness: 60,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin_addr, &[]),
+        ExecuteMsg::SetPriceFeed {
+            denom: "inj".to_string(),
+            feed_id: Binary::from(vec![2u8; 32]),
+        },
+    )
+    .unwrap();
+
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, .. } if contract_addr == "oracle_contract" => {
+            let response = pyth::PriceFeedResponse {
+                price_feed: pyth::PriceFeed {
+                    id: Binary::from(vec![2u8; 32]),
+                    price: pyth::Price {
+                        price: 10_000_000,
+                        conf: 0,
+                        expo: -6,
+                        publish_time: now as i64,
+                    },
+                    ema_price: pyth::Price {
+                        price: 9_000_000,
+                        conf: 0,
+                        expo: -6,
+                        publish_time: now as i64,
+                    },
+                },
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        // Anything else is the Astroport router's swap simulation, which
+        // `execute_deposit` cross-checks the oracle valuation against. Quote
+        // the same 90 the EMA price implies so the two agree.
+        WasmQuery::Smart { .. } => {
+            let response = crate::token_converter::SimulateSwapResponse {
+                amount: Uint128::from(90u128),
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        _ => SystemResult::Err(SystemError::InvalidRequest {
+            error: "Unexpected wasm query type".to_string(),
+            request: Default::default(),
+        }),
+    });
+}
+
 #[test]
 fn test_deposit() {
     let mut deps = mock_dependencies();
@@ -14,7 +86,7 @@ fn test_deposit() {
     let deposit_amount = 100u128;
     let user_addr = Addr::unchecked(user_address());
     let info = message_info(&user_addr, &coins(deposit_amount, DENOM));
-    let msg = ExecuteMsg::Deposit {};
+    let msg = ExecuteMsg::Deposit { recipient: None };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert!(res
@@ -32,10 +104,8 @@ fn test_deposit() {
     )
     .unwrap();
     let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
-    assert_eq!(
-        user_info.user_info.total_usdc_value,
-        Uint128::from(deposit_amount)
-    );
+    assert_eq!(user_info.asset_value, Uint128::from(deposit_amount));
+    assert_eq!(user_info.shares, Uint128::from(deposit_amount));
 }
 
 #[test]
@@ -47,7 +117,7 @@ fn test_deposit_with_unsupported_denom() {
     let deposit_amount = 100u128;
     let user_addr = Addr::unchecked(user_address());
     let info = message_info(&user_addr, &coins(deposit_amount, "unsupported"));
-    let msg = ExecuteMsg::Deposit {};
+    let msg = ExecuteMsg::Deposit { recipient: None };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
 
@@ -79,7 +149,7 @@ fn test_deposit_with_multiple_denoms() {
     ];
 
     let info = message_info(&user_addr, &funds);
-    let msg = ExecuteMsg::Deposit {};
+    let msg = ExecuteMsg::Deposit { recipient: None };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
 
@@ -95,6 +165,238 @@ fn test_deposit_with_multiple_denoms() {
     }
 }
 
+#[test]
+fn test_deposit_values_non_base_denom_via_oracle() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let env = mock_env();
+    setup_inj_oracle(&mut deps, env.block.time.seconds());
+
+    let user_addr = Addr::unchecked(user_address());
+    let info = message_info(&user_addr, &coins(10u128, "inj"));
+    execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Deposit { recipient: None }).unwrap();
+
+    let query_res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::GetUserInfo {
+            address: user_address(),
+        },
+    )
+    .unwrap();
+    let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
+
+    // The EMA price (9.0) is lower than spot (10.0), so the conservative
+    // valuation of 10 inj is 90, not 100.
+    assert_eq!(user_info.asset_value, Uint128::from(90u128));
+}
+
+#[test]
+fn test_deposit_rejects_oracle_router_divergence() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin_addr = Addr::unchecked(admin_address());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin_addr, &[]),
+        ExecuteMsg::SetOracleConfig {
+            oracle_addr: "oracle_contract".to_string(),
+            max_staleness: 60,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin_addr, &[]),
+        ExecuteMsg::SetPriceFeed {
+            denom: "inj".to_string(),
+            feed_id: Binary::from(vec![2u8; 32]),
+        },
+    )
+    .unwrap();
+
+    let now = mock_env().block.time.seconds();
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, .. } if contract_addr == "oracle_contract" => {
+            let response = pyth::PriceFeedResponse {
+                price_feed: pyth::PriceFeed {
+                    id: Binary::from(vec![2u8; 32]),
+                    price: pyth::Price {
+                        price: 10_000_000,
+                        conf: 0,
+                        expo: -6,
+                        publish_time: now as i64,
+                    },
+                    ema_price: pyth::Price {
+                        price: 9_000_000,
+                        conf: 0,
+                        expo: -6,
+                        publish_time: now as i64,
+                    },
+                },
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        // The router quotes 10 inj at only 50 USDC, far below the oracle's
+        // 90 USDC valuation — more than setup_contract's 1% max_slippage
+        // apart, so the deposit should be rejected rather than credited.
+        WasmQuery::Smart { .. } => {
+            let response = crate::token_converter::SimulateSwapResponse {
+                amount: Uint128::from(50u128),
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        _ => SystemResult::Err(SystemError::InvalidRequest {
+            error: "Unexpected wasm query type".to_string(),
+            request: Default::default(),
+        }),
+    });
+
+    let user_addr = Addr::unchecked(user_address());
+    let info = message_info(&user_addr, &coins(10u128, "inj"));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Deposit { recipient: None },
+    )
+    .unwrap_err();
+    assert!(format!("{:?}", err).contains("OracleDivergence"));
+}
+
+#[test]
+fn test_get_price_feeds_lists_registered_denoms() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    setup_inj_oracle(&mut deps, mock_env().block.time.seconds());
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::GetPriceFeeds {}).unwrap();
+    let feeds: crate::msg::GetPriceFeedsResponse = from_json(&query_res).unwrap();
+
+    assert_eq!(feeds.max_staleness, 60);
+    assert_eq!(feeds.feeds.len(), 1);
+    assert_eq!(feeds.feeds[0].denom, "inj");
+    assert_eq!(feeds.feeds[0].feed_id, Binary::from(vec![2u8; 32]));
+}
+
+#[test]
+fn test_get_deposit_quote_matches_conservative_oracle_value() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let env = mock_env();
+    setup_inj_oracle(&mut deps, env.block.time.seconds());
+
+    let query_res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::GetDepositQuote {
+            denom: "inj".to_string(),
+            amount: Uint128::from(10u128),
+        },
+    )
+    .unwrap();
+    let quote: GetDepositQuoteResponse = from_json(&query_res).unwrap();
+    assert_eq!(quote.usdc_value, Uint128::from(90u128));
+}
+
+/// Mocks the Astroport router's `SimulateSwapOperations` query so any swap
+/// simulates a return of `simulated_out`, regardless of which contract
+/// address `config.astroport_router` validates to.
+fn setup_router_swap_quote(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
+    simulated_out: u128,
+) {
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { .. } => {
+            let response = crate::token_converter::SimulateSwapResponse {
+                amount: Uint128::from(simulated_out),
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        _ => SystemResult::Err(SystemError::InvalidRequest {
+            error: "Unexpected wasm query type".to_string(),
+            request: Default::default(),
+        }),
+    });
+}
+
+#[test]
+fn test_deposit_without_oracle_feed_queues_a_router_swap() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    setup_router_swap_quote(&mut deps, 98);
+
+    let user_addr = Addr::unchecked(user_address());
+    let info = message_info(&user_addr, &coins(10u128, "inj"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Deposit { recipient: None },
+    )
+    .unwrap();
+
+    // No oracle feed is registered for "inj", so the deposit is routed
+    // through Astroport as a submessage rather than credited immediately.
+    assert_eq!(res.messages.len(), 1);
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "status" && attr.value == "pending_swap"));
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetUserInfo {
+            address: user_address(),
+        },
+    )
+    .unwrap();
+    let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
+    assert_eq!(user_info.asset_value, Uint128::zero());
+}
+
+#[test]
+fn test_deposit_swap_reply_credits_actual_amount_received() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    setup_router_swap_quote(&mut deps, 98);
+
+    let user_addr = Addr::unchecked(user_address());
+    let info = message_info(&user_addr, &coins(10u128, "inj"));
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { recipient: None }).unwrap();
+
+    // Simulate the router's swap submessage succeeding with an actual
+    // return amount that may differ from the pre-swap simulation.
+    let reply_msg = Reply {
+        id: 1,
+        payload: Binary::default(),
+        gas_used: 0,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![Event::new("wasm").add_attribute("return_amount", "95")],
+            data: None,
+            msg_responses: vec![],
+        }),
+    };
+    reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetUserInfo {
+            address: user_address(),
+        },
+    )
+    .unwrap();
+    let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
+    assert_eq!(user_info.asset_value, Uint128::from(95u128));
+}
+
 #[test]
 fn test_deposit_with_no_funds() {
     let mut deps = mock_dependencies();
@@ -103,7 +405,7 @@ fn test_deposit_with_no_funds() {
     // Try to deposit with no funds
     let user_addr = Addr::unchecked(user_address());
     let info = message_info(&user_addr, &[]);
-    let msg = ExecuteMsg::Deposit {};
+    let msg = ExecuteMsg::Deposit { recipient: None };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
 
@@ -118,3 +420,179 @@ fn test_deposit_with_no_funds() {
         _ => panic!("Expected an error"),
     }
 }
+
+#[test]
+fn test_deposit_with_explicit_recipient_matches_default_to_self() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let deposit_amount = 100u128;
+    let user_addr = Addr::unchecked(user_address());
+    let info = message_info(&user_addr, &coins(deposit_amount, DENOM));
+    let msg = ExecuteMsg::Deposit {
+        recipient: Some(user_address()),
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "depositor" && attr.value == user_address()));
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "recipient" && attr.value == user_address()));
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetUserInfo {
+            address: user_address(),
+        },
+    )
+    .unwrap();
+    let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
+    assert_eq!(user_info.asset_value, Uint128::from(deposit_amount));
+}
+
+#[test]
+fn test_deposit_credits_third_party_recipient_not_sender() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let deposit_amount = 100u128;
+    let sender_addr = Addr::unchecked(user_address());
+    let recipient_addr = Addr::unchecked("recipient_user");
+    let info = message_info(&sender_addr, &coins(deposit_amount, DENOM));
+    let msg = ExecuteMsg::Deposit {
+        recipient: Some(recipient_addr.to_string()),
+    };
+
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetUserInfo {
+            address: recipient_addr.to_string(),
+        },
+    )
+    .unwrap();
+    let recipient_info: GetUserInfoResponse = from_json(&query_res).unwrap();
+    assert_eq!(recipient_info.asset_value, Uint128::from(deposit_amount));
+
+    // The sender funded the deposit but holds none of the resulting shares.
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetUserInfo {
+            address: user_address(),
+        },
+    )
+    .unwrap();
+    let sender_info: GetUserInfoResponse = from_json(&query_res).unwrap();
+    assert_eq!(sender_info.asset_value, Uint128::zero());
+}
+
+#[test]
+fn test_deposit_rejects_zero_value_conversion() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let user_addr = Addr::unchecked(user_address());
+    let info = message_info(&user_addr, &coins(0u128, DENOM));
+    let msg = ExecuteMsg::Deposit { recipient: None };
+
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(format!("{:?}", err).contains("ZeroValueDeposit"));
+}
+
+#[test]
+fn test_deposit_dust_conversion_creates_no_phantom_user_info() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin_addr = Addr::unchecked(admin_address());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin_addr, &[]),
+        ExecuteMsg::SetOracleConfig {
+            oracle_addr: "oracle_contract".to_string(),
+            max_staleness: 60,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin_addr, &[]),
+        ExecuteMsg::SetPriceFeed {
+            denom: "inj".to_string(),
+            feed_id: Binary::from(vec![2u8; 32]),
+        },
+    )
+    .unwrap();
+
+    // A price of 0.000001 means a 1-unit deposit's conservative valuation
+    // rounds all the way down to zero USDC.
+    let now = mock_env().block.time.seconds();
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, .. } if contract_addr == "oracle_contract" => {
+            let response = pyth::PriceFeedResponse {
+                price_feed: pyth::PriceFeed {
+                    id: Binary::from(vec![2u8; 32]),
+                    price: pyth::Price {
+                        price: 1,
+                        conf: 0,
+                        expo: -6,
+                        publish_time: now as i64,
+                    },
+                    ema_price: pyth::Price {
+                        price: 1,
+                        conf: 0,
+                        expo: -6,
+                        publish_time: now as i64,
+                    },
+                },
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        // The router's spot quote rounds to zero too, so the divergence
+        // check doesn't reject this before the zero-value guard does.
+        WasmQuery::Smart { .. } => {
+            let response = crate::token_converter::SimulateSwapResponse {
+                amount: Uint128::zero(),
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        _ => SystemResult::Err(SystemError::InvalidRequest {
+            error: "Unexpected wasm query type".to_string(),
+            request: Default::default(),
+        }),
+    });
+
+    let user_addr = Addr::unchecked(user_address());
+    let info = message_info(&user_addr, &coins(1u128, "inj"));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Deposit { recipient: None },
+    )
+    .unwrap_err();
+    assert!(format!("{:?}", err).contains("ZeroValueDeposit"));
+
+    // No phantom UserInfo with shares but no backing value was created.
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetUserInfo {
+            address: user_address(),
+        },
+    )
+    .unwrap();
+    let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
+    assert_eq!(user_info.asset_value, Uint128::zero());
+    assert_eq!(user_info.shares, Uint128::zero());
+}
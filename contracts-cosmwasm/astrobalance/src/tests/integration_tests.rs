@@ -8,8 +8,10 @@ use crate::msg::{
     ExecuteMsg, GetProtocolInfoResponse, GetProtocolsResponse, GetRebalanceHistoryResponse,
     GetTotalValueResponse, GetUserInfoResponse, QueryMsg,
 };
+use crate::state::ContractStatus;
 use crate::tests::common::*;
 use crate::tests::protocol_tests::setup_test_protocols;
+use crate::ContractError;
 
 // Helper function to set up a test environment with protocols
 fn setup_integration_test() -> (
@@ -37,7 +39,13 @@ fn test_full_lifecycle() {
     // Step 1: User deposits funds
     let deposit_amount = 1000u128;
     let info = message_info(&user, &coins(deposit_amount, DENOM));
-    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Deposit { recipient: None },
+    )
+    .unwrap();
 
     assert!(res
         .attributes
@@ -56,7 +64,7 @@ fn test_full_lifecycle() {
     let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
 
     assert_eq!(
-        user_info.user_info.total_usdc_value,
+        user_info.asset_value,
         Uint128::new(deposit_amount)
     );
 
@@ -71,6 +79,7 @@ fn test_full_lifecycle() {
     let msg = ExecuteMsg::Rebalance {
         target_allocations: new_allocations.clone(),
         reason: "Initial rebalance after deposit".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -78,6 +87,7 @@ fn test_full_lifecycle() {
         .attributes
         .iter()
         .any(|attr| attr.key == "method" && attr.value == "rebalance"));
+    settle_rebalance(&mut deps, &res);
 
     // Verify protocols have updated allocations
     let query_res = query(deps.as_ref(), mock_env(), QueryMsg::GetProtocols {}).unwrap();
@@ -99,6 +109,8 @@ fn test_full_lifecycle() {
     let msg = ExecuteMsg::Withdraw {
         amount: Uint128::new(withdrawal_amount),
         denom: Some(DENOM.to_string()),
+        sync_balances: None,
+        exact_output: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -119,8 +131,8 @@ fn test_full_lifecycle() {
     let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
 
     // Should be original deposit minus withdrawal (approximately, due to potential fees)
-    assert!(user_info.user_info.total_usdc_value.u128() < deposit_amount);
-    assert!(user_info.user_info.total_usdc_value.u128() >= deposit_amount - withdrawal_amount - 2); // Allow small rounding difference
+    assert!(user_info.asset_value.u128() < deposit_amount);
+    assert!(user_info.asset_value.u128() >= deposit_amount - withdrawal_amount - 2); // Allow small rounding difference
 
     // Step 4: Admin updates a protocol (disables it)
     let info = message_info(&admin, &[]);
@@ -160,6 +172,7 @@ fn test_full_lifecycle() {
     let msg = ExecuteMsg::Rebalance {
         target_allocations: new_allocations.clone(),
         reason: "Rebalance after disabling hydro".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -167,22 +180,52 @@ fn test_full_lifecycle() {
         .attributes
         .iter()
         .any(|attr| attr.key == "method" && attr.value == "rebalance"));
+    settle_rebalance(&mut deps, &res);
 
     // Check rebalance history records
     let query_res = query(
         deps.as_ref(),
         mock_env(),
-        QueryMsg::GetRebalanceHistory { limit: None },
+        QueryMsg::GetRebalanceHistory {
+            start_after: None,
+            limit: None,
+        },
     )
     .unwrap();
     let history: GetRebalanceHistoryResponse = from_json(&query_res).unwrap();
 
     assert_eq!(history.history.len(), 2);
-    assert_eq!(history.history[0].reason, "Rebalance after disabling hydro");
+    assert_eq!(history.history[0].detail, "Rebalance after disabling hydro");
+
+    // Step 6: Admin halts the contract; deposits are rejected but users can
+    // still exit via emergency withdraw
+    let info = message_info(&admin, &[]);
+    let msg = ExecuteMsg::SetContractStatus {
+        status: ContractStatus::StopAll,
+        reason: "halting for maintenance".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = message_info(&user, &coins(100u128, DENOM));
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Deposit { recipient: None },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::OperationPaused {
+            status: ContractStatus::StopAll
+        }
+    );
 
-    // Step 6: User emergency withdraws remaining funds
+    // Step 7: User emergency withdraws remaining funds
     let info = message_info(&user, &[]);
-    let msg = ExecuteMsg::EmergencyWithdraw {};
+    let msg = ExecuteMsg::EmergencyWithdraw {
+        sync_balances: None,
+    };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert!(res
@@ -201,7 +244,7 @@ fn test_full_lifecycle() {
     .unwrap();
     let user_info: GetUserInfoResponse = from_json(&query_res).unwrap();
 
-    assert_eq!(user_info.user_info.total_usdc_value, Uint128::zero());
+    assert_eq!(user_info.asset_value, Uint128::zero());
 }
 
 #[test]
@@ -212,12 +255,12 @@ fn test_multi_user_scenario() {
     // User 1 deposits
     let deposit_amount1 = 1000u128;
     let info = message_info(&user1, &coins(deposit_amount1, DENOM));
-    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { recipient: None }).unwrap();
 
     // User 2 deposits
     let deposit_amount2 = 2000u128;
     let info = message_info(&user2, &coins(deposit_amount2, DENOM));
-    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { recipient: None }).unwrap();
 
     // AI operator rebalances
     let allocations = vec![
@@ -230,14 +273,18 @@ fn test_multi_user_scenario() {
     let msg = ExecuteMsg::Rebalance {
         target_allocations: allocations.clone(),
         reason: "Initial balance".to_string(),
+        sync_balances: None,
     };
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    settle_rebalance(&mut deps, &res);
 
     // User 1 withdraws half
     let info = message_info(&user1, &[]);
     let msg = ExecuteMsg::Withdraw {
         amount: Uint128::new(deposit_amount1 / 2),
         denom: Some(DENOM.to_string()),
+        sync_balances: None,
+        exact_output: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -263,22 +310,26 @@ fn test_multi_user_scenario() {
     let user2_info: GetUserInfoResponse = from_json(&query_res).unwrap();
 
     // User 1 should have approximately half their deposit left
-    assert!(user1_info.user_info.total_usdc_value.u128() <= deposit_amount1 / 2 + 5);
-    assert!(user1_info.user_info.total_usdc_value.u128() >= deposit_amount1 / 2 - 5);
+    assert!(user1_info.asset_value.u128() <= deposit_amount1 / 2 + 5);
+    assert!(user1_info.asset_value.u128() >= deposit_amount1 / 2 - 5);
 
     // User 2 should still have their full deposit
     assert_eq!(
-        user2_info.user_info.total_usdc_value,
+        user2_info.asset_value,
         Uint128::new(deposit_amount2)
     );
 
     // Both users withdraw all remaining funds
     let info = message_info(&user1, &[]);
-    let msg = ExecuteMsg::EmergencyWithdraw {};
+    let msg = ExecuteMsg::EmergencyWithdraw {
+        sync_balances: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let info = message_info(&user2, &[]);
-    let msg = ExecuteMsg::EmergencyWithdraw {};
+    let msg = ExecuteMsg::EmergencyWithdraw {
+        sync_balances: None,
+    };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Check contract total value should be zero or very close to it
@@ -0,0 +1,371 @@
+use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
+use cosmwasm_std::{from_json, Addr, Decimal};
+
+use crate::contract::{execute, query};
+use crate::msg::{Config, ExecuteMsg, GetRolesResponse, QueryMsg};
+use crate::permissions::Role;
+use crate::tests::common::*;
+
+#[test]
+fn test_grant_role_allows_delegated_rebalance() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin = Addr::unchecked(admin_address());
+    let committee = Addr::unchecked("risk_committee");
+
+    // Before the grant, the committee can't touch risk parameters.
+    let info = message_info(&committee, &[]);
+    let msg = ExecuteMsg::UpdateRiskParameters {
+        risk_parameters: default_risk_parameters(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(e) => assert!(format!("{:?}", e).contains("Unauthorized")),
+        _ => panic!("Expected an error"),
+    }
+
+    // Admin delegates ParamManager to the committee.
+    let info = message_info(&admin, &[]);
+    let msg = ExecuteMsg::GrantRole {
+        address: committee.to_string(),
+        role: Role::ParamManager,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // The committee can now update risk parameters.
+    let info = message_info(&committee, &[]);
+    let msg = ExecuteMsg::UpdateRiskParameters {
+        risk_parameters: default_risk_parameters(),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res: GetRolesResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoles {
+                address: committee.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.roles, vec![Role::ParamManager]);
+}
+
+#[test]
+fn test_revoke_role_removes_delegated_access() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin = Addr::unchecked(admin_address());
+    let committee = Addr::unchecked("risk_committee");
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::GrantRole {
+            address: committee.to_string(),
+            role: Role::ParamManager,
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::RevokeRole {
+            address: committee.to_string(),
+            role: Role::ParamManager,
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateRiskParameters {
+        risk_parameters: default_risk_parameters(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), message_info(&committee, &[]), msg);
+    match res {
+        Err(e) => assert!(format!("{:?}", e).contains("Unauthorized")),
+        _ => panic!("Expected an error"),
+    }
+
+    let res: GetRolesResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoles {
+                address: committee.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.roles.is_empty());
+}
+
+#[test]
+fn test_grant_revoke_role_requires_admin() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let user = Addr::unchecked(user_address());
+    let msg = ExecuteMsg::GrantRole {
+        address: user.to_string(),
+        role: Role::Admin,
+    };
+    let res = execute(deps.as_mut(), mock_env(), message_info(&user, &[]), msg);
+    match res {
+        Err(e) => assert!(format!("{:?}", e).contains("Unauthorized")),
+        _ => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn test_ai_operator_holds_rebalancer_role_by_default() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let operator = Addr::unchecked(operator_address());
+    let res: GetRolesResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoles {
+                address: operator.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.roles, vec![Role::Rebalancer]);
+}
+
+#[test]
+fn test_accept_admin_requires_proposed_address() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin = Addr::unchecked(admin_address());
+    let successor = Addr::unchecked("successor");
+    let stranger = Addr::unchecked("stranger");
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::UpdateAdmin {
+            admin: successor.to_string(),
+        },
+    )
+    .unwrap();
+
+    // The old admin still controls the contract until the handover is accepted.
+    let config: Config =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    assert_eq!(config.admin, admin);
+    assert_eq!(config.pending_admin, Some(successor.clone()));
+
+    // Anyone other than the proposed successor is rejected.
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&stranger, &[]),
+        ExecuteMsg::AcceptAdmin {},
+    );
+    match res {
+        Err(e) => assert!(format!("{:?}", e).contains("Unauthorized")),
+        _ => panic!("Expected an error"),
+    }
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&successor, &[]),
+        ExecuteMsg::AcceptAdmin {},
+    )
+    .unwrap();
+
+    let config: Config =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    assert_eq!(config.admin, successor);
+    assert_eq!(config.pending_admin, None);
+}
+
+#[test]
+fn test_cancel_admin_change_clears_pending_without_error_when_proposed() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin = Addr::unchecked(admin_address());
+    let successor = Addr::unchecked("successor");
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::UpdateAdmin {
+            admin: successor.to_string(),
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::CancelAdminChange {},
+    )
+    .unwrap();
+
+    let config: Config =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    assert_eq!(config.admin, admin);
+    assert_eq!(config.pending_admin, None);
+
+    // The successor's now-stale acceptance no longer has anything to accept.
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&successor, &[]),
+        ExecuteMsg::AcceptAdmin {},
+    );
+    match res {
+        Err(e) => assert!(format!("{:?}", e).contains("Unauthorized")),
+        _ => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn test_cancel_admin_change_without_pending_proposal_errors() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin = Addr::unchecked(admin_address());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::CancelAdminChange {},
+    );
+    match res {
+        Err(e) => assert!(format!("{:?}", e).contains("NoPendingChange")),
+        _ => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn test_accept_ai_operator_requires_proposed_address() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin = Addr::unchecked(admin_address());
+    let successor = Addr::unchecked("successor_operator");
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::UpdateAiOperator {
+            ai_operator: successor.to_string(),
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&successor, &[]),
+        ExecuteMsg::AcceptAiOperator {},
+    )
+    .unwrap();
+
+    let config: Config =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    assert_eq!(config.ai_operator, successor);
+    assert_eq!(config.pending_ai_operator, None);
+}
+
+#[test]
+fn test_accept_ai_operator_moves_rebalancer_role() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+
+    let admin = Addr::unchecked(admin_address());
+    let old_operator = Addr::unchecked(operator_address());
+    let successor = Addr::unchecked("successor_operator");
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::UpdateAiOperator {
+            ai_operator: successor.to_string(),
+        },
+    )
+    .unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&successor, &[]),
+        ExecuteMsg::AcceptAiOperator {},
+    )
+    .unwrap();
+
+    // The old operator no longer holds Rebalancer ...
+    let res: GetRolesResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoles {
+                address: old_operator.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.roles.is_empty());
+
+    // ... and can no longer rebalance.
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&old_operator, &[]),
+        ExecuteMsg::AutoRebalance {},
+    );
+    match res {
+        Err(e) => assert!(format!("{:?}", e).contains("Unauthorized")),
+        _ => panic!("Expected an error"),
+    }
+
+    // The new operator holds Rebalancer in its place.
+    let res: GetRolesResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRoles {
+                address: successor.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.roles, vec![Role::Rebalancer]);
+}
+
+fn default_risk_parameters() -> crate::msg::RiskParametersMsg {
+    crate::msg::RiskParametersMsg {
+        max_allocation_per_protocol: Decimal::percent(50),
+        max_slippage: Decimal::percent(1),
+        rebalance_threshold: Decimal::percent(5),
+        emergency_withdrawal_fee: Decimal::percent(2),
+        max_price_staleness: 60,
+        performance_fee: Decimal::percent(10),
+        max_price_deviation: Decimal::percent(5),
+        max_slippage_bps: Decimal::percent(1),
+    }
+}
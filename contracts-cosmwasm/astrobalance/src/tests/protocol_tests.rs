@@ -1,11 +1,18 @@
 use cosmwasm_std::testing::{
     message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage,
 };
-use cosmwasm_std::{from_json, Addr, Decimal, Empty, OwnedDeps, Uint128};
-use std::str::FromStr;
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, Addr, Binary, ContractResult, CosmosMsg, Decimal, Empty,
+    OwnedDeps, SystemError, SystemResult, Uint128, WasmMsg, WasmQuery,
+};
 
 use crate::contract::{execute, query};
-use crate::msg::{ExecuteMsg, GetProtocolInfoResponse, GetProtocolsResponse, QueryMsg};
+use crate::msg::{
+    ExecuteMsg, GetProtocolInfoResponse, GetProtocolsResponse, QueryMsg,
+    ReconcileTotalValueResponse,
+};
+use crate::oracle;
+use crate::state::AssetInfo;
 use crate::tests::common::*;
 
 // Helper function that adds test protocols with unchecked addresses
@@ -17,6 +24,7 @@ pub fn setup_test_protocols(deps: &mut OwnedDeps<MockStorage, MockApi, MockQueri
         name: "helix".to_string(),
         contract_addr: "contract_helix".to_string(), // Simple string that won't be validated
         initial_allocation: Decimal::percent(30),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(
         deps.as_mut(),
@@ -31,6 +39,7 @@ pub fn setup_test_protocols(deps: &mut OwnedDeps<MockStorage, MockApi, MockQueri
         name: "hydro".to_string(),
         contract_addr: "contract_hydro".to_string(), // Simple string
         initial_allocation: Decimal::percent(30),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(
         deps.as_mut(),
@@ -45,6 +54,7 @@ pub fn setup_test_protocols(deps: &mut OwnedDeps<MockStorage, MockApi, MockQueri
         name: "neptune".to_string(),
         contract_addr: "contract_neptune".to_string(), // Simple string
         initial_allocation: Decimal::percent(40),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(
         deps.as_mut(),
@@ -69,6 +79,7 @@ fn test_add_protocol() {
         name: "test_protocol".to_string(),
         contract_addr: contract_addr.to_string(),
         initial_allocation: allocation,
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -110,6 +121,7 @@ fn test_add_multiple_protocols_with_allocations() {
         name: "protocol1".to_string(),
         contract_addr: "contract_protocol1".to_string(),
         initial_allocation: Decimal::percent(30),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -119,6 +131,7 @@ fn test_add_multiple_protocols_with_allocations() {
         name: "protocol2".to_string(),
         contract_addr: "contract_protocol2".to_string(),
         initial_allocation: Decimal::percent(30),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -128,6 +141,7 @@ fn test_add_multiple_protocols_with_allocations() {
         name: "protocol3".to_string(),
         contract_addr: "contract_protocol3".to_string(),
         initial_allocation: Decimal::percent(40),
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -173,6 +187,7 @@ fn test_add_protocol_with_invalid_allocation() {
         name: "test_protocol".to_string(),
         contract_addr: "contract_test".to_string(),
         initial_allocation: excessive_allocation,
+        deposit_asset: AssetInfo::Native(DENOM.to_string()),
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -329,14 +344,8 @@ fn test_remove_protocol() {
         .map(|p| p.allocation_percentage)
         .sum();
 
-    // Check that the total allocation is either exactly 1 or very close to it
-    // (the specific value 0.999999999999999999 is what decimal operations are producing)
-    assert!(
-        total_allocation == Decimal::one()
-            || total_allocation == Decimal::from_str("0.999999999999999999").unwrap(),
-        "Total allocation should be 100% or very close to it, but was: {}",
-        total_allocation
-    );
+    // Largest-remainder normalization guarantees an exact 100% sum, no dust.
+    assert_eq!(total_allocation, Decimal::one());
 }
 
 #[test]
@@ -385,3 +394,156 @@ fn test_query_protocol_balances() {
     // In a real scenario, balances would be updated after a deposit or rebalance
     // Through the update_protocol_balances function
 }
+
+#[test]
+fn test_reconcile_total_value_detects_drift_against_stored_balances() {
+    let mut deps = mock_dependencies();
+    mock_protocol_response(&mut deps);
+    setup_contract(deps.as_mut());
+    setup_test_protocols(&mut deps);
+
+    // Stored balances are still zero for every protocol; the mocked adapters report
+    // real deposits of 100 + 150 + 200, so the reconciliation should surface that drift.
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ReconcileTotalValue {},
+    )
+    .unwrap();
+    let reconciled: ReconcileTotalValueResponse = from_json(&query_res).unwrap();
+
+    assert_eq!(reconciled.stored_total, Uint128::zero());
+    assert_eq!(reconciled.live_total, Uint128::from(450u128));
+    assert_eq!(reconciled.drift, Uint128::from(450u128));
+    assert!(reconciled.live_exceeds_stored);
+    assert_eq!(reconciled.protocols.len(), 3);
+}
+
+// Registers a Pyth feed for both `DENOM` (the base denom) and "inj" and
+// mocks the oracle contract to answer either feed id with a fixed price: 1.0
+// for the base denom, 10.0 for "inj". Lets a test assert that distributing
+// to a protocol whose `deposit_asset` isn't the base denom funds it with the
+// converted asset quantity, not the raw USD value.
+fn setup_base_and_inj_oracle(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
+    base_feed_id: Binary,
+    inj_feed_id: Binary,
+    now: i64,
+) {
+    let admin = Addr::unchecked(admin_address());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::SetOracleConfig {
+            oracle_addr: "oracle_contract".to_string(),
+            max_staleness: 60,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::SetPriceFeed {
+            denom: DENOM.to_string(),
+            feed_id: base_feed_id.clone(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::SetPriceFeed {
+            denom: "inj".to_string(),
+            feed_id: inj_feed_id.clone(),
+        },
+    )
+    .unwrap();
+
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, msg } if contract_addr == "oracle_contract" => {
+            let oracle::pyth::QueryMsg::PriceFeed { id } = from_json(msg).unwrap();
+            let price = if id == base_feed_id {
+                oracle::pyth::Price {
+                    price: 1_000_000,
+                    conf: 0,
+                    expo: -6,
+                    publish_time: now,
+                }
+            } else {
+                oracle::pyth::Price {
+                    price: 10_000_000,
+                    conf: 0,
+                    expo: -6,
+                    publish_time: now,
+                }
+            };
+            let response = oracle::pyth::PriceFeedResponse {
+                price_feed: oracle::pyth::PriceFeed {
+                    id,
+                    price,
+                    ema_price: price,
+                },
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        }
+        _ => SystemResult::Err(SystemError::InvalidRequest {
+            error: "Unexpected wasm query type".to_string(),
+            request: Default::default(),
+        }),
+    });
+}
+
+#[test]
+fn test_distribute_deposit_converts_usd_value_to_deposit_asset_amount() {
+    let mut deps = mock_dependencies();
+    setup_contract(deps.as_mut());
+    let admin = Addr::unchecked(admin_address());
+
+    setup_base_and_inj_oracle(
+        &mut deps,
+        Binary::from(vec![1u8; 32]),
+        Binary::from(vec![2u8; 32]),
+        mock_env().block.time.seconds() as i64,
+    );
+
+    // "helix" doubles as the adapter type key `create_protocol_adapter`
+    // dispatches on, so this protocol is funded through `route_deposit`
+    // rather than the AMM adapter's own internal swap.
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        ExecuteMsg::AddProtocol {
+            name: "helix".to_string(),
+            contract_addr: "contract_helix".to_string(),
+            initial_allocation: Decimal::percent(100),
+            deposit_asset: AssetInfo::Native("inj".to_string()),
+        },
+    )
+    .unwrap();
+
+    let user = Addr::unchecked(user_address());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&user, &coins(1000u128, DENOM)),
+        ExecuteMsg::Deposit { recipient: None },
+    )
+    .unwrap();
+
+    let funded_amount = res.messages.iter().find_map(|sub_msg| match &sub_msg.msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds,
+            ..
+        }) if contract_addr == "contract_helix" => Some(funds[0].amount),
+        _ => None,
+    });
+
+    // 1000 USD worth of an asset priced at 10.0 (against a 1.0 base) is 100
+    // units, not the raw 1000 a pre-fix build would have attached as funds.
+    assert_eq!(funded_amount, Some(Uint128::new(100)));
+}
@@ -1,13 +1,14 @@
 use cosmwasm_std::testing::{
     message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage,
 };
-use cosmwasm_std::{coins, from_json, Addr, Decimal, Empty, OwnedDeps};
+use cosmwasm_std::{coins, from_json, Addr, Decimal, Empty, OwnedDeps, Uint128};
 
 use crate::contract::{execute, instantiate, query};
 use crate::msg::{
-    ExecuteMsg, GetProtocolsResponse, GetRebalanceHistoryResponse, InstantiateMsg, QueryMsg,
-    RiskParametersMsg,
+    ExecuteMsg, GetHarvestHistoryResponse, GetProtocolsResponse, GetRebalanceHistoryResponse,
+    GetTotalValueResponse, InstantiateMsg, QueryMsg, RiskParametersMsg,
 };
+use crate::state::AssetInfo;
 use crate::tests::common::*;
 use crate::tests::protocol_tests::setup_test_protocols;
 
@@ -26,7 +27,7 @@ fn setup_rebalance_test() -> (
     let deposit_amount = 1000u128;
     let user = Addr::unchecked(user_address());
     let info = message_info(&user, &coins(deposit_amount, DENOM));
-    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { recipient: None }).unwrap();
 
     let admin = Addr::unchecked(admin_address());
     let operator = Addr::unchecked(operator_address());
@@ -50,6 +51,7 @@ fn test_rebalance_basic() {
     let msg = ExecuteMsg::Rebalance {
         target_allocations: new_allocations.clone(),
         reason: "Test rebalance".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -60,6 +62,8 @@ fn test_rebalance_basic() {
         .iter()
         .any(|attr| attr.key == "method" && attr.value == "rebalance"));
 
+    settle_rebalance(&mut deps, &res);
+
     // Query protocols to verify new allocations
     let query_res = query(deps.as_ref(), mock_env(), QueryMsg::GetProtocols {}).unwrap();
     let protocols_info: GetProtocolsResponse = from_json(&query_res).unwrap();
@@ -79,14 +83,17 @@ fn test_rebalance_basic() {
     let query_res = query(
         deps.as_ref(),
         mock_env(),
-        QueryMsg::GetRebalanceHistory { limit: None },
+        QueryMsg::GetRebalanceHistory {
+            start_after: None,
+            limit: None,
+        },
     )
     .unwrap();
     let history: GetRebalanceHistoryResponse = from_json(&query_res).unwrap();
 
     assert_eq!(history.history.len(), 1);
-    assert_eq!(history.history[0].reason, "Test rebalance");
-    assert_eq!(history.history[0].initiated_by, operator);
+    assert_eq!(history.history[0].detail, "Test rebalance");
+    assert_eq!(history.history[0].actor, operator);
 }
 
 #[test]
@@ -103,6 +110,7 @@ fn test_rebalance_unauthorized() {
             ("neptune".to_string(), Decimal::percent(40)),
         ],
         reason: "Unauthorized rebalance".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -130,6 +138,7 @@ fn test_rebalance_invalid_allocations() {
             ("neptune".to_string(), Decimal::percent(30)),
         ],
         reason: "Invalid allocations".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -156,6 +165,7 @@ fn test_rebalance_invalid_total_allocation() {
             ("helix".to_string(), Decimal::percent(101)), // Exceeds max 100%
         ],
         reason: "Invalid total allocation".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -183,14 +193,24 @@ fn test_rebalance_excessive_allocation() {
         admin: admin_address(),
         ai_operator: operator_address(),
         base_denom: DENOM.to_string(),
-        accepted_denoms: vec![DENOM.to_string(), "inj".to_string()],
+        accepted_denoms: vec![
+            AssetInfo::Native(DENOM.to_string()),
+            AssetInfo::Native("inj".to_string()),
+        ],
         astroport_router: router_address(),
         risk_parameters: RiskParametersMsg {
             max_allocation_per_protocol: Decimal::percent(40),
             max_slippage: Decimal::percent(1),
             rebalance_threshold: Decimal::percent(5),
             emergency_withdrawal_fee: Decimal::percent(1),
+            max_price_staleness: 60,
+            performance_fee: Decimal::percent(10),
+            max_price_deviation: Decimal::percent(5),
+            max_slippage_bps: Decimal::percent(1),
         },
+        unbonding_period: None,
+        performance_fee_bps: 1000,
+        fee_collector: fee_collector_address(),
     };
 
     let info = message_info(&Addr::unchecked(creator_address()), &[]);
@@ -201,7 +221,7 @@ fn test_rebalance_excessive_allocation() {
     let deposit_amount = 1000u128;
     let user = Addr::unchecked(user_address());
     let info = message_info(&user, &coins(deposit_amount, DENOM));
-    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit { recipient: None }).unwrap();
 
     // Try to execute rebalance with one protocol exceeding max allocation
     let operator = Addr::unchecked(operator_address());
@@ -213,6 +233,7 @@ fn test_rebalance_excessive_allocation() {
             ("neptune".to_string(), Decimal::percent(25)),
         ],
         reason: "Allocation exceeding max per protocol".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -244,9 +265,11 @@ fn test_multiple_rebalances() {
     let msg = ExecuteMsg::Rebalance {
         target_allocations: first_allocations.clone(),
         reason: "First rebalance".to_string(),
+        sync_balances: None,
     };
 
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    settle_rebalance(&mut deps, &res);
 
     // Second rebalance
     let second_allocations = vec![
@@ -259,23 +282,28 @@ fn test_multiple_rebalances() {
     let msg = ExecuteMsg::Rebalance {
         target_allocations: second_allocations.clone(),
         reason: "Second rebalance".to_string(),
+        sync_balances: None,
     };
 
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    settle_rebalance(&mut deps, &res);
 
     // Query rebalance history
     let query_res = query(
         deps.as_ref(),
         mock_env(),
-        QueryMsg::GetRebalanceHistory { limit: None },
+        QueryMsg::GetRebalanceHistory {
+            start_after: None,
+            limit: None,
+        },
     )
     .unwrap();
     let history: GetRebalanceHistoryResponse = from_json(&query_res).unwrap();
 
     // Verify both rebalances were recorded in history
     assert_eq!(history.history.len(), 2);
-    assert_eq!(history.history[0].reason, "Second rebalance");
-    assert_eq!(history.history[1].reason, "First rebalance");
+    assert_eq!(history.history[0].detail, "Second rebalance");
+    assert_eq!(history.history[1].detail, "First rebalance");
 
     // Verify final allocations
     let query_res = query(deps.as_ref(), mock_env(), QueryMsg::GetProtocols {}).unwrap();
@@ -305,6 +333,7 @@ fn test_rebalance_with_nonexistent_protocol() {
             ("nonexistent".to_string(), Decimal::percent(40)),
         ],
         reason: "Nonexistent protocol".to_string(),
+        sync_balances: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -318,3 +347,108 @@ fn test_rebalance_with_nonexistent_protocol() {
         _ => panic!("Expected an error"),
     }
 }
+
+#[test]
+fn test_rebalance_blocked_by_static_limiter() {
+    let (mut deps, admin, operator) = setup_rebalance_test();
+
+    // Cap helix well below what the rebalance below requests
+    let register_msg = ExecuteMsg::RegisterStaticLimiter {
+        protocol: "helix".to_string(),
+        upper_bound: Decimal::percent(35),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        register_msg,
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Rebalance {
+        target_allocations: vec![
+            ("helix".to_string(), Decimal::percent(40)),
+            ("hydro".to_string(), Decimal::percent(20)),
+            ("neptune".to_string(), Decimal::percent(40)),
+        ],
+        reason: "Push past static limiter".to_string(),
+        sync_balances: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), message_info(&operator, &[]), msg);
+
+    assert!(res.is_err());
+    assert!(format!("{:?}", res.unwrap_err()).contains("AllocationChangeTooLarge"));
+}
+
+#[test]
+fn test_rebalance_blocked_by_change_limiter() {
+    let (mut deps, admin, operator) = setup_rebalance_test();
+
+    // Helix starts at 30%; only allow a 5-point move per rebalance
+    let register_msg = ExecuteMsg::RegisterChangeLimiter {
+        protocol: "helix".to_string(),
+        boundary_offset: Decimal::percent(5),
+        window_size: 3600,
+        division_count: 6,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        message_info(&admin, &[]),
+        register_msg,
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Rebalance {
+        target_allocations: vec![
+            ("helix".to_string(), Decimal::percent(60)),
+            ("hydro".to_string(), Decimal::percent(20)),
+            ("neptune".to_string(), Decimal::percent(20)),
+        ],
+        reason: "Jump far beyond the moving average".to_string(),
+        sync_balances: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), message_info(&operator, &[]), msg);
+
+    assert!(res.is_err());
+    assert!(format!("{:?}", res.unwrap_err()).contains("AllocationChangeTooLarge"));
+}
+
+#[test]
+fn test_harvest_without_compound_credits_total_value() {
+    let (mut deps, _admin, operator) = setup_rebalance_test();
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::GetTotalValue {}).unwrap();
+    let before: GetTotalValueResponse = from_json(&query_res).unwrap();
+
+    let msg = ExecuteMsg::HarvestRewards { compound: Some(false) };
+    let res = execute(deps.as_mut(), mock_env(), message_info(&operator, &[]), msg).unwrap();
+
+    // One claim submessage per enabled protocol (helix, hydro, neptune).
+    assert_eq!(res.messages.len(), 3);
+
+    settle_harvest(&mut deps, &res, &[10u128, 20u128, 30u128]);
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::GetTotalValue {}).unwrap();
+    let after: GetTotalValueResponse = from_json(&query_res).unwrap();
+
+    // The full claimed total (60) lands in the vault's own balance and must
+    // count toward the share price even though it wasn't redeposited into
+    // any protocol.
+    assert_eq!(after.total_value, before.total_value + Uint128::from(60u128));
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::GetHarvestHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: GetHarvestHistoryResponse = from_json(&query_res).unwrap();
+    assert_eq!(history.history.len(), 1);
+    assert_eq!(history.history[0].amount, Uint128::from(60u128));
+}
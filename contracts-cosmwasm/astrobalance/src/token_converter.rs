@@ -1,4 +1,7 @@
 use crate::error::ContractError;
+use crate::protocols::cw20;
+use crate::state::{AssetInfo, PAIR_REGISTRY};
+use crate::swap_math;
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
     to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, StdResult, Uint128, WasmMsg,
@@ -6,41 +9,162 @@ use cosmwasm_std::{
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+// Pair-registry lookups are order-independent: normalize to whichever label
+// sorts first so `RegisterPair` and the converters agree on the key.
+pub fn normalized_pair_key(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+fn to_astroport_asset_info(asset: &AssetInfo) -> astroport::AssetInfo {
+    match asset {
+        AssetInfo::Native(denom) => astroport::AssetInfo::NativeToken {
+            denom: denom.clone(),
+        },
+        AssetInfo::Cw20(addr) => astroport::AssetInfo::Token {
+            contract_addr: addr.to_string(),
+        },
+    }
+}
+
+// Builds the message(s) that pay `amount` of `from_asset` into `contract_addr`
+// to execute `exec_msg` there: attached as native `funds` for
+// `AssetInfo::Native`, or a prior `Cw20ExecuteMsg::IncreaseAllowance` message
+// ahead of the (fund-less) exec call for `AssetInfo::Cw20` - the pair/router
+// mock's `Swap`/`ExecuteSwapOperations` pull the cw20 leg via allowance
+// rather than a `Send` hook, unlike the vault's own `Receive` entry point.
+fn route_swap_input(
+    from_asset: &AssetInfo,
+    contract_addr: &Addr,
+    amount: Uint128,
+    exec_msg: cosmwasm_std::Binary,
+) -> StdResult<Vec<CosmosMsg>> {
+    match from_asset {
+        AssetInfo::Native(denom) => Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: exec_msg,
+            funds: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        })]),
+        AssetInfo::Cw20(cw20_addr) => Ok(vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: cw20_addr.to_string(),
+                msg: to_json_binary(&cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: contract_addr.to_string(),
+                    amount,
+                    expires: None,
+                })?,
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: exec_msg,
+                funds: vec![],
+            }),
+        ]),
+    }
+}
+
+// Swaps `amount` of `from_asset` for `to_asset` directly against a registered
+// Astroport pair contract, bypassing the router entirely. Uses `max_spread`
+// (Astroport's own on-chain slippage guard) rather than `belief_price`, since
+// the pair has no notion of an off-chain reference price here.
+fn swap_via_pair(
+    pair_contract: &Addr,
+    from_asset: &AssetInfo,
+    amount: Uint128,
+    simulated_out: Uint128,
+    max_slippage: Decimal,
+) -> StdResult<(Vec<CosmosMsg>, Uint128)> {
+    let offer_asset = astroport::pair::Asset {
+        info: to_astroport_asset_info(from_asset),
+        amount,
+    };
+
+    let swap_exec = to_json_binary(&astroport::pair::ExecuteMsg::Swap {
+        offer_asset,
+        belief_price: None,
+        max_spread: Some(max_slippage),
+        to: None,
+    })?;
+
+    let messages = route_swap_input(from_asset, pair_contract, amount, swap_exec)?;
+
+    Ok((messages, simulated_out))
+}
+
+fn simulate_via_pair(
+    deps: Deps,
+    pair_contract: &Addr,
+    from_asset: &AssetInfo,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let simulation: astroport::pair::SimulationResponse = deps.querier.query_wasm_smart(
+        pair_contract.to_string(),
+        &astroport::pair::QueryMsg::Simulation {
+            offer_asset: astroport::pair::Asset {
+                info: to_astroport_asset_info(from_asset),
+                amount,
+            },
+        },
+    )?;
+    Ok(simulation.return_amount)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct AstroportRouter(pub Addr);
 
+// `AssetInfo::Native("usdc")` is the one asset `base_denom` is always
+// instantiated with; every converter below short-circuits on it the same
+// way, so pull the check out once.
+fn is_usdc(asset: &AssetInfo) -> bool {
+    matches!(asset, AssetInfo::Native(denom) if denom == "usdc")
+}
+
 impl AstroportRouter {
-    // Convert any supported token to USDC
+    // Convert any supported asset to USDC
     pub fn convert_to_usdc(
         &self,
         deps: Deps,
-        denom: &str,
+        asset: &AssetInfo,
         amount: Uint128,
         max_slippage: Decimal,
-    ) -> StdResult<(CosmosMsg, Uint128)> {
+    ) -> StdResult<(Vec<CosmosMsg>, Uint128)> {
         // If already USDC, no conversion needed
-        if denom == "usdc" {
+        if is_usdc(asset) {
             return Ok((
-                CosmosMsg::Bank(BankMsg::Send {
+                vec![CosmosMsg::Bank(BankMsg::Send {
                     to_address: self.0.to_string(),
                     amount: vec![Coin {
-                        denom: denom.to_string(),
+                        denom: asset.label(),
                         amount,
                     }],
-                }),
+                })],
                 amount,
             ));
         }
 
+        // Prefer a directly registered pair over the router's extra hop.
+        if let Some(pair_contract) =
+            PAIR_REGISTRY.may_load(deps.storage, normalized_pair_key(&asset.label(), "usdc"))?
+        {
+            let simulated_out = simulate_via_pair(deps, &pair_contract, asset, amount)?;
+            let min_expected = swap_math::min_receive(simulated_out, max_slippage);
+            return swap_via_pair(&pair_contract, asset, amount, min_expected, max_slippage);
+        }
+
         // Query Astroport for estimated return
         let simulate_swap: SimulateSwapResponse = deps.querier.query_wasm_smart(
             self.0.to_string(),
             &astroport::QueryMsg::SimulateSwapOperations {
                 offer_amount: amount,
                 operations: vec![astroport::SwapOperation::AstroSwap {
-                    offer_asset_info: astroport::AssetInfo::NativeToken {
-                        denom: denom.to_string(),
-                    },
+                    offer_asset_info: to_astroport_asset_info(asset),
                     ask_asset_info: astroport::AssetInfo::NativeToken {
                         denom: "usdc".to_string(),
                     },
@@ -49,56 +173,55 @@ impl AstroportRouter {
         )?;
 
         // Calculate minimum expected with slippage
-        let min_expected = simulate_swap.amount.multiply_ratio(
-            Uint128::new(1_000_000) - max_slippage.atomics(),
-            Uint128::new(1_000_000),
-        );
+        let min_expected = swap_math::min_receive(simulate_swap.amount, max_slippage);
 
         // Create the swap message
-        let swap_msg = WasmMsg::Execute {
-            contract_addr: self.0.to_string(),
-            msg: to_json_binary(&astroport::ExecuteMsg::ExecuteSwapOperations {
-                operations: vec![astroport::SwapOperation::AstroSwap {
-                    offer_asset_info: astroport::AssetInfo::NativeToken {
-                        denom: denom.to_string(),
-                    },
-                    ask_asset_info: astroport::AssetInfo::NativeToken {
-                        denom: "usdc".to_string(),
-                    },
-                }],
-                minimum_receive: Some(min_expected),
-            })?,
-            funds: vec![Coin {
-                denom: denom.to_string(),
-                amount,
+        let swap_exec = to_json_binary(&astroport::ExecuteMsg::ExecuteSwapOperations {
+            operations: vec![astroport::SwapOperation::AstroSwap {
+                offer_asset_info: to_astroport_asset_info(asset),
+                ask_asset_info: astroport::AssetInfo::NativeToken {
+                    denom: "usdc".to_string(),
+                },
             }],
-        };
+            minimum_receive: Some(min_expected),
+        })?;
+        let messages = route_swap_input(asset, &self.0, amount, swap_exec)?;
 
-        Ok((CosmosMsg::Wasm(swap_msg), simulate_swap.amount))
+        Ok((messages, simulate_swap.amount))
     }
 
-    // Convert USDC to requested token
+    // Convert USDC to requested asset
     pub fn convert_from_usdc(
         &self,
         deps: Deps,
-        to_denom: &str,
+        to_asset: &AssetInfo,
         amount: Uint128,
         max_slippage: Decimal,
-    ) -> StdResult<(CosmosMsg, Uint128)> {
+    ) -> StdResult<(Vec<CosmosMsg>, Uint128)> {
         // If requesting USDC, no conversion needed
-        if to_denom == "usdc" {
+        if is_usdc(to_asset) {
             return Ok((
-                CosmosMsg::Bank(BankMsg::Send {
+                vec![CosmosMsg::Bank(BankMsg::Send {
                     to_address: self.0.to_string(),
                     amount: vec![Coin {
-                        denom: to_denom.to_string(),
+                        denom: to_asset.label(),
                         amount,
                     }],
-                }),
+                })],
                 amount,
             ));
         }
 
+        // Prefer a directly registered pair over the router's extra hop.
+        if let Some(pair_contract) =
+            PAIR_REGISTRY.may_load(deps.storage, normalized_pair_key("usdc", &to_asset.label()))?
+        {
+            let usdc = AssetInfo::Native("usdc".to_string());
+            let simulated_out = simulate_via_pair(deps, &pair_contract, &usdc, amount)?;
+            let min_expected = swap_math::min_receive(simulated_out, max_slippage);
+            return swap_via_pair(&pair_contract, &usdc, amount, min_expected, max_slippage);
+        }
+
         // Query Astroport for estimated return
         let simulate_swap: SimulateSwapResponse = deps.querier.query_wasm_smart(
             self.0.to_string(),
@@ -108,20 +231,16 @@ impl AstroportRouter {
                     offer_asset_info: astroport::AssetInfo::NativeToken {
                         denom: "usdc".to_string(),
                     },
-                    ask_asset_info: astroport::AssetInfo::NativeToken {
-                        denom: to_denom.to_string(),
-                    },
+                    ask_asset_info: to_astroport_asset_info(to_asset),
                 }],
             },
         )?;
 
         // Calculate minimum expected with slippage
-        let min_expected = simulate_swap.amount.multiply_ratio(
-            Uint128::new(1_000_000) - max_slippage.atomics(),
-            Uint128::new(1_000_000),
-        );
+        let min_expected = swap_math::min_receive(simulate_swap.amount, max_slippage);
 
-        // Create the swap message
+        // Create the swap message. USDC is always native, so this leg never
+        // needs the cw20 allowance dance `route_swap_input` handles.
         let swap_msg = WasmMsg::Execute {
             contract_addr: self.0.to_string(),
             msg: to_json_binary(&astroport::ExecuteMsg::ExecuteSwapOperations {
@@ -129,9 +248,7 @@ impl AstroportRouter {
                     offer_asset_info: astroport::AssetInfo::NativeToken {
                         denom: "usdc".to_string(),
                     },
-                    ask_asset_info: astroport::AssetInfo::NativeToken {
-                        denom: to_denom.to_string(),
-                    },
+                    ask_asset_info: to_astroport_asset_info(to_asset),
                 }],
                 minimum_receive: Some(min_expected),
             })?,
@@ -141,34 +258,44 @@ impl AstroportRouter {
             }],
         };
 
-        Ok((CosmosMsg::Wasm(swap_msg), simulate_swap.amount))
+        Ok((vec![CosmosMsg::Wasm(swap_msg)], simulate_swap.amount))
     }
 
     // Get price quote for UI preview - doesn't execute a swap
     pub fn get_price_quote(
         &self,
         deps: Deps,
-        from_denom: &str,
-        to_denom: &str,
+        from_asset: &AssetInfo,
+        to_asset: &AssetInfo,
         amount: Uint128,
     ) -> Result<Uint128, ContractError> {
-        // If same token, 1:1 rate
-        if from_denom == to_denom {
+        // If same asset, 1:1 rate
+        if from_asset == to_asset {
             return Ok(amount);
         }
 
-        // Determine swap direction
-        let (offer_denom, ask_denom, _is_to_usdc) = if to_denom == "usdc" {
-            (from_denom, "usdc", true)
-        } else if from_denom == "usdc" {
-            ("usdc", to_denom, false)
-        } else {
-            // For non-USDC pairs, we need to do a double hop through USDC
-            // First get quote from from_denom -> USDC
-            let usdc_amount = self.get_price_quote(deps, from_denom, "usdc", amount)?;
-            // Then get quote from USDC -> to_denom
-            return self.get_price_quote(deps, "usdc", to_denom, usdc_amount);
-        };
+        // One side must be USDC for a direct quote; otherwise double-hop
+        // through it.
+        if !is_usdc(from_asset) && !is_usdc(to_asset) {
+            let usdc = AssetInfo::Native("usdc".to_string());
+            // First get quote from from_asset -> USDC
+            let usdc_amount = self.get_price_quote(deps, from_asset, &usdc, amount)?;
+            // Then get quote from USDC -> to_asset
+            return self.get_price_quote(deps, &usdc, to_asset, usdc_amount);
+        }
+        let (offer_asset, ask_asset) = (from_asset, to_asset);
+
+        // Prefer a directly registered pair over the router's extra hop.
+        if let Some(pair_contract) = PAIR_REGISTRY.may_load(
+            deps.storage,
+            normalized_pair_key(&offer_asset.label(), &ask_asset.label()),
+        )? {
+            return simulate_via_pair(deps, &pair_contract, offer_asset, amount).map_err(|err| {
+                ContractError::ConversionError {
+                    error: format!("Failed to get price quote: {}", err),
+                }
+            });
+        }
 
         // Query Astroport for simulated swap
         let simulate_result: StdResult<SimulateSwapResponse> = deps.querier.query_wasm_smart(
@@ -176,12 +303,8 @@ impl AstroportRouter {
             &astroport::QueryMsg::SimulateSwapOperations {
                 offer_amount: amount,
                 operations: vec![astroport::SwapOperation::AstroSwap {
-                    offer_asset_info: astroport::AssetInfo::NativeToken {
-                        denom: offer_denom.to_string(),
-                    },
-                    ask_asset_info: astroport::AssetInfo::NativeToken {
-                        denom: ask_denom.to_string(),
-                    },
+                    offer_asset_info: to_astroport_asset_info(offer_asset),
+                    ask_asset_info: to_astroport_asset_info(ask_asset),
                 }],
             },
         );
@@ -198,15 +321,15 @@ impl AstroportRouter {
     pub fn safe_convert_to_usdc(
         &self,
         deps: Deps,
-        denom: &str,
+        asset: &AssetInfo,
         amount: Uint128,
         max_slippage: Decimal,
-    ) -> Result<(CosmosMsg, Uint128), ContractError> {
+    ) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
         if amount.is_zero() {
             return Err(ContractError::InvalidAmount {});
         }
 
-        match self.convert_to_usdc(deps, denom, amount, max_slippage) {
+        match self.convert_to_usdc(deps, asset, amount, max_slippage) {
             Ok(result) => Ok(result),
             Err(err) => Err(ContractError::ConversionError {
                 error: format!("Failed to convert to USDC: {}", err),
@@ -214,19 +337,190 @@ impl AstroportRouter {
         }
     }
 
-    // Safe version with error handling for contract usage
-    pub fn safe_convert_from_usdc(
+    // Converts `amount` of `from_denom` directly into `to_denom`, for legs
+    // that don't pass through USDC (e.g. a rebalance moving value straight
+    // from one protocol's deposit denom to another's). Cross-checks the
+    // router's `SimulateSwapOperations` quote against the constant-product
+    // estimate computed from the pool's own reserves - the same
+    // manipulation-resistant check `AstroportAmmAdapter` applies to its own
+    // pool - and rejects with `ExcessiveSlippage` if they diverge by more
+    // than `max_slippage`, rather than trusting the router's simulation alone.
+    //
+    // Both legs are native denoms in practice (rebalances move value between
+    // protocols' own deposit denoms, not cw20 assets), so unlike the
+    // USDC-anchored converters above this one doesn't thread `AssetInfo`.
+    pub fn convert_denom(
         &self,
         deps: Deps,
+        from_denom: &str,
         to_denom: &str,
         amount: Uint128,
         max_slippage: Decimal,
     ) -> Result<(CosmosMsg, Uint128), ContractError> {
+        if from_denom == to_denom {
+            return Ok((
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: self.0.to_string(),
+                    amount: vec![Coin {
+                        denom: to_denom.to_string(),
+                        amount,
+                    }],
+                }),
+                amount,
+            ));
+        }
+
+        let simulate_swap: SimulateSwapResponse = deps.querier.query_wasm_smart(
+            self.0.to_string(),
+            &astroport::QueryMsg::SimulateSwapOperations {
+                offer_amount: amount,
+                operations: vec![astroport::SwapOperation::AstroSwap {
+                    offer_asset_info: astroport::AssetInfo::NativeToken {
+                        denom: from_denom.to_string(),
+                    },
+                    ask_asset_info: astroport::AssetInfo::NativeToken {
+                        denom: to_denom.to_string(),
+                    },
+                }],
+            },
+        )?;
+
+        let reserves: PoolReservesResponse = deps.querier.query_wasm_smart(
+            self.0.to_string(),
+            &astroport::QueryMsg::PoolReserves {
+                offer_asset_info: astroport::AssetInfo::NativeToken {
+                    denom: from_denom.to_string(),
+                },
+                ask_asset_info: astroport::AssetInfo::NativeToken {
+                    denom: to_denom.to_string(),
+                },
+            },
+        )?;
+
+        let expected_out =
+            swap_math::expected_output(reserves.offer_reserve, reserves.ask_reserve, amount);
+        if expected_out.is_zero() {
+            return Err(ContractError::ExcessiveSlippage {});
+        }
+
+        let divergence =
+            Decimal::from_ratio(expected_out.abs_diff(simulate_swap.amount), expected_out);
+        if divergence > max_slippage {
+            return Err(ContractError::ExcessiveSlippage {});
+        }
+
+        let min_expected = swap_math::min_receive(expected_out, max_slippage);
+
+        let swap_msg = WasmMsg::Execute {
+            contract_addr: self.0.to_string(),
+            msg: to_json_binary(&astroport::ExecuteMsg::ExecuteSwapOperations {
+                operations: vec![astroport::SwapOperation::AstroSwap {
+                    offer_asset_info: astroport::AssetInfo::NativeToken {
+                        denom: from_denom.to_string(),
+                    },
+                    ask_asset_info: astroport::AssetInfo::NativeToken {
+                        denom: to_denom.to_string(),
+                    },
+                }],
+                minimum_receive: Some(min_expected),
+            })?,
+            funds: vec![Coin {
+                denom: from_denom.to_string(),
+                amount,
+            }],
+        };
+
+        Ok((CosmosMsg::Wasm(swap_msg), simulate_swap.amount))
+    }
+
+    // Sizes and builds a swap for an exact *output* of `to_asset`, the
+    // inverse of `convert_from_usdc`'s exact-input swap: given the quantity
+    // the caller wants to receive, queries the registered pair's
+    // `ReverseSimulation` for the USDC it costs rather than simulating an
+    // output from a fixed input. Only available via a directly registered
+    // pair (see `PAIR_REGISTRY`/`RegisterPair`) - the router's own interface
+    // has no reverse-simulation equivalent, so there's no fallback path the
+    // way `convert_from_usdc` has one. `max_slippage` bounds the USDC spent
+    // above the quote rather than the output below a quote, since the
+    // amount returned here is fixed by construction.
+    pub fn convert_from_usdc_exact_out(
+        &self,
+        deps: Deps,
+        to_asset: &AssetInfo,
+        want_out: Uint128,
+        max_slippage: Decimal,
+    ) -> Result<(CosmosMsg, Uint128), ContractError> {
+        if want_out.is_zero() {
+            return Err(ContractError::InvalidAmount {});
+        }
+
+        let pair_contract = PAIR_REGISTRY
+            .may_load(deps.storage, normalized_pair_key("usdc", &to_asset.label()))?
+            .ok_or_else(|| ContractError::ConversionError {
+                error: format!(
+                    "no registered pair to size an exact-output swap into {}",
+                    to_asset.label()
+                ),
+            })?;
+
+        let reverse_simulation: astroport::pair::ReverseSimulationResponse = deps
+            .querier
+            .query_wasm_smart(
+                pair_contract.to_string(),
+                &astroport::pair::QueryMsg::ReverseSimulation {
+                    ask_asset: astroport::pair::Asset {
+                        info: to_astroport_asset_info(to_asset),
+                        amount: want_out,
+                    },
+                },
+            )
+            .map_err(|err| ContractError::ConversionError {
+                error: format!("Failed to reverse-simulate exact-output swap: {}", err),
+            })?;
+
+        let slippage_buffer = reverse_simulation
+            .offer_amount
+            .multiply_ratio(max_slippage.numerator(), max_slippage.denominator());
+        let bounded_in = reverse_simulation.offer_amount.saturating_add(slippage_buffer);
+
+        // USDC is always native, so paying into the pair is a plain
+        // `Swap` call with attached funds - no cw20 allowance leg here even
+        // when `to_asset` itself is a cw20.
+        let swap_msg = WasmMsg::Execute {
+            contract_addr: pair_contract.to_string(),
+            msg: to_json_binary(&astroport::pair::ExecuteMsg::Swap {
+                offer_asset: astroport::pair::Asset {
+                    info: astroport::AssetInfo::NativeToken {
+                        denom: "usdc".to_string(),
+                    },
+                    amount: bounded_in,
+                },
+                belief_price: Some(Decimal::from_ratio(bounded_in, want_out)),
+                max_spread: Some(max_slippage),
+                to: None,
+            })?,
+            funds: vec![Coin {
+                denom: "usdc".to_string(),
+                amount: bounded_in,
+            }],
+        };
+
+        Ok((CosmosMsg::Wasm(swap_msg), bounded_in))
+    }
+
+    // Safe version with error handling for contract usage
+    pub fn safe_convert_from_usdc(
+        &self,
+        deps: Deps,
+        to_asset: &AssetInfo,
+        amount: Uint128,
+        max_slippage: Decimal,
+    ) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
         if amount.is_zero() {
             return Err(ContractError::InvalidAmount {});
         }
 
-        match self.convert_from_usdc(deps, to_denom, amount, max_slippage) {
+        match self.convert_from_usdc(deps, to_asset, amount, max_slippage) {
             Ok(result) => Ok(result),
             Err(err) => Err(ContractError::ConversionError {
                 error: format!("Failed to convert from USDC: {}", err),
@@ -269,6 +563,54 @@ pub mod astroport {
             offer_amount: Uint128,
             operations: Vec<SwapOperation>,
         },
+        PoolReserves {
+            offer_asset_info: AssetInfo,
+            ask_asset_info: AssetInfo,
+        },
+    }
+
+    // The single-pair contract interface, queried/executed directly against
+    // a `PAIR_REGISTRY` entry instead of hopping through the router above.
+    pub mod pair {
+        use super::AssetInfo;
+        use cosmwasm_schema::cw_serde;
+        use cosmwasm_std::{Decimal, Uint128};
+
+        #[cw_serde]
+        pub struct Asset {
+            pub info: AssetInfo,
+            pub amount: Uint128,
+        }
+
+        #[cw_serde]
+        pub enum ExecuteMsg {
+            Swap {
+                offer_asset: Asset,
+                belief_price: Option<Decimal>,
+                max_spread: Option<Decimal>,
+                to: Option<String>,
+            },
+        }
+
+        #[cw_serde]
+        pub enum QueryMsg {
+            Simulation { offer_asset: Asset },
+            ReverseSimulation { ask_asset: Asset },
+        }
+
+        #[cw_serde]
+        pub struct SimulationResponse {
+            pub return_amount: Uint128,
+            pub spread_amount: Uint128,
+            pub commission_amount: Uint128,
+        }
+
+        #[cw_serde]
+        pub struct ReverseSimulationResponse {
+            pub offer_amount: Uint128,
+            pub spread_amount: Uint128,
+            pub commission_amount: Uint128,
+        }
     }
 }
 
@@ -276,3 +618,9 @@ pub mod astroport {
 pub struct SimulateSwapResponse {
     pub amount: Uint128,
 }
+
+#[cw_serde]
+pub struct PoolReservesResponse {
+    pub offer_reserve: Uint128,
+    pub ask_reserve: Uint128,
+}
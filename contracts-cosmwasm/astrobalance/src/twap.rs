@@ -0,0 +1,178 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Storage, Timestamp};
+use cw_storage_plus::Map;
+
+use crate::error::ContractError;
+
+/// Cumulative-price TWAP tracking for one denom, updated every time
+/// `update_twap` observes a fresh Astroport spot quote. Modeled after
+/// Uniswap V2's accumulator: `price_cumulative` only ever grows (by
+/// `last_price * elapsed_seconds` each update), so a TWAP between any two
+/// observations is the difference in cumulatives divided by the elapsed
+/// time between them. Unlike Uniswap V2, callers here don't take their own
+/// snapshots - `twap_since_genesis` always averages from `genesis_time`,
+/// the first time this denom was ever observed, trading off a fixed
+/// averaging window for not needing a second stored checkpoint per denom.
+#[cw_serde]
+pub struct TwapState {
+    pub price_cumulative: Decimal,
+    pub last_price: Decimal,
+    pub last_update: Timestamp,
+    // Never changes after the first `update_twap` call for this denom; the
+    // fixed start of the averaging window `twap_since_genesis` divides by.
+    pub genesis_time: Timestamp,
+}
+
+/// Keyed by `AssetInfo::label()`, same as `PAIR_REGISTRY`'s denom keys.
+pub const TWAP_STATE: Map<&str, TwapState> = Map::new("twap_state");
+
+/// Folds `current_price` into `denom`'s accumulator: extends
+/// `price_cumulative` by the previous `last_price` held over the interval
+/// since `last_update`, then refreshes `last_price`/`last_update` to the new
+/// observation. The first call for a denom just seeds the state at
+/// `current_price` with zero accumulated cumulative, since there's no prior
+/// price to have held over any interval yet.
+pub fn update_twap(
+    storage: &mut dyn Storage,
+    denom: &str,
+    current_price: Decimal,
+    now: Timestamp,
+) -> Result<TwapState, ContractError> {
+    let state = match TWAP_STATE.may_load(storage, denom)? {
+        None => TwapState {
+            price_cumulative: Decimal::zero(),
+            last_price: current_price,
+            last_update: now,
+            genesis_time: now,
+        },
+        Some(mut state) => {
+            let elapsed = now.seconds().saturating_sub(state.last_update.seconds());
+            state.price_cumulative += state.last_price * Decimal::from_ratio(elapsed, 1u128);
+            state.last_price = current_price;
+            state.last_update = now;
+            state
+        }
+    };
+
+    TWAP_STATE.save(storage, denom, &state)?;
+    Ok(state)
+}
+
+/// The time-weighted average price for `denom` since it was first observed,
+/// extrapolated up to `now` using the price held since `last_update`. Returns
+/// `None` if `denom` has never been observed, or if fewer than `window_secs`
+/// have elapsed since `genesis_time` - too little history to trust the
+/// average, so callers should skip any deviation check rather than compare
+/// against a near-instantaneous TWAP.
+pub fn twap_since_genesis(
+    storage: &dyn Storage,
+    denom: &str,
+    window_secs: u64,
+    now: Timestamp,
+) -> Result<Option<Decimal>, ContractError> {
+    let Some(state) = TWAP_STATE.may_load(storage, denom)? else {
+        return Ok(None);
+    };
+
+    let elapsed_total = now.seconds().saturating_sub(state.genesis_time.seconds());
+    if elapsed_total < window_secs {
+        return Ok(None);
+    }
+    if elapsed_total == 0 {
+        return Ok(Some(state.last_price));
+    }
+
+    let elapsed_since_update = now.seconds().saturating_sub(state.last_update.seconds());
+    let cumulative_now = state.price_cumulative
+        + state.last_price * Decimal::from_ratio(elapsed_since_update, 1u128);
+
+    Ok(Some(cumulative_now / Decimal::from_ratio(elapsed_total, 1u128)))
+}
+
+/// Rejects a live `spot` quote that diverges from `twap` by more than
+/// `max_deviation`, the same relative-divergence shape
+/// `token_converter::convert_denom` uses to cross-check the router's quote
+/// against constant-product reserves.
+pub fn check_price_deviation(
+    spot: Decimal,
+    twap: Decimal,
+    max_deviation: Decimal,
+) -> Result<(), ContractError> {
+    if twap.is_zero() {
+        return Ok(());
+    }
+
+    let divergence = Decimal::from_ratio(spot.atomics().abs_diff(twap.atomics()), twap.atomics());
+    if divergence > max_deviation {
+        return Err(ContractError::PriceDeviationTooHigh {});
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn first_observation_seeds_genesis_with_no_history() {
+        let mut storage = MockStorage::new();
+        let now = Timestamp::from_seconds(1_000);
+
+        update_twap(&mut storage, "inj", Decimal::percent(800), now).unwrap();
+
+        // No time has elapsed since genesis yet, so any window requires a
+        // skip.
+        assert_eq!(twap_since_genesis(&storage, "inj", 1, now).unwrap(), None);
+    }
+
+    #[test]
+    fn averages_price_over_elapsed_time() {
+        let mut storage = MockStorage::new();
+        let t0 = Timestamp::from_seconds(1_000);
+        update_twap(&mut storage, "inj", Decimal::percent(800), t0).unwrap();
+
+        // Price held at 8.00 for 100s, then moves to 10.00.
+        let t1 = t0.plus_seconds(100);
+        update_twap(&mut storage, "inj", Decimal::percent(1_000), t1).unwrap();
+
+        // Querying right at t1: all 100s of history averaged at 8.00.
+        let twap = twap_since_genesis(&storage, "inj", 100, t1).unwrap().unwrap();
+        assert_eq!(twap, Decimal::percent(800));
+
+        // 100 more seconds at 10.00: (8*100 + 10*100) / 200 = 9.00.
+        let t2 = t1.plus_seconds(100);
+        let twap = twap_since_genesis(&storage, "inj", 100, t2).unwrap().unwrap();
+        assert_eq!(twap, Decimal::percent(900));
+    }
+
+    #[test]
+    fn skips_when_window_exceeds_accumulated_history() {
+        let mut storage = MockStorage::new();
+        let t0 = Timestamp::from_seconds(1_000);
+        update_twap(&mut storage, "inj", Decimal::percent(800), t0).unwrap();
+
+        let t1 = t0.plus_seconds(50);
+        assert_eq!(twap_since_genesis(&storage, "inj", 3600, t1).unwrap(), None);
+    }
+
+    #[test]
+    fn unobserved_denom_has_no_twap() {
+        let storage = MockStorage::new();
+        assert_eq!(
+            twap_since_genesis(&storage, "inj", 0, Timestamp::from_seconds(1)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn check_price_deviation_rejects_beyond_max() {
+        let twap = Decimal::percent(1_000);
+        assert!(check_price_deviation(Decimal::percent(1_040), twap, Decimal::percent(5)).is_ok());
+        assert_eq!(
+            check_price_deviation(Decimal::percent(1_060), twap, Decimal::percent(5)),
+            Err(ContractError::PriceDeviationTooHigh {})
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Order, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Bound, Item, Map};
+
+/// What moved in a `TxRecord`, one variant per user- or operator-triggered
+/// event this contract tracks.
+#[cw_serde]
+pub enum TxKind {
+    Deposit,
+    Withdraw,
+    Rebalance,
+    EmergencyWithdraw,
+    Harvest,
+}
+
+/// One entry in the append-only transaction ledger, keyed by a monotonic
+/// sequence number (`TX_SEQ`) rather than appended to a single stored vector,
+/// so reading history never requires deserializing more than `limit` entries
+/// at a time — the SNIP-20 transaction-history convention.
+#[cw_serde]
+pub struct TxRecord {
+    pub seq: u64,
+    pub height: u64,
+    pub timestamp: Timestamp,
+    pub actor: Addr,
+    pub kind: TxKind,
+    pub amount: Uint128,
+    pub detail: String,
+}
+
+pub const TX_SEQ: Item<u64> = Item::new("tx_seq");
+pub const TX_LOG: Map<u64, TxRecord> = Map::new("tx_log");
+
+/// Appends a new `TxRecord` at the next sequence number.
+#[allow(clippy::too_many_arguments)]
+pub fn append_tx(
+    storage: &mut dyn Storage,
+    height: u64,
+    timestamp: Timestamp,
+    actor: Addr,
+    kind: TxKind,
+    amount: Uint128,
+    detail: String,
+) -> StdResult<()> {
+    let seq = TX_SEQ.may_load(storage)?.unwrap_or_default();
+
+    TX_LOG.save(
+        storage,
+        seq,
+        &TxRecord {
+            seq,
+            height,
+            timestamp,
+            actor,
+            kind,
+            amount,
+            detail,
+        },
+    )?;
+    TX_SEQ.save(storage, &(seq + 1))
+}
+
+/// Pages through the ledger newest-first, optionally filtered to `actor` or
+/// `kind`, starting strictly before `start_after` (an already-seen `seq`,
+/// for the next page) and capped at `limit` (default 20, max 100 — same
+/// bounds `GetRebalanceHistory` used on its old in-memory vector).
+pub fn query_tx_log(
+    storage: &dyn Storage,
+    actor: Option<&Addr>,
+    kind: Option<&TxKind>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<TxRecord>> {
+    let limit = limit.unwrap_or(20).min(100) as usize;
+    let bound = start_after.map(Bound::exclusive);
+
+    let mut out = vec![];
+    for item in TX_LOG.range(storage, None, bound, Order::Descending) {
+        let (_, record) = item?;
+
+        if actor.is_some_and(|a| &record.actor != a) {
+            continue;
+        }
+        if kind.is_some_and(|k| &record.kind != k) {
+            continue;
+        }
+
+        out.push(record);
+        if out.len() >= limit {
+            break;
+        }
+    }
+
+    Ok(out)
+}